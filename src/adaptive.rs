@@ -0,0 +1,114 @@
+use http::HeaderMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Semaphore;
+use tracing::{debug, info};
+
+/// Requests in flight when adaptive concurrency starts
+const ADAPTIVE_START: usize = 2;
+
+/// Grow while at least this share of the hourly quota remains
+const HEALTHY_QUOTA: f64 = 0.5;
+
+/// Halve concurrency once less than this share of the quota remains
+const LOW_QUOTA: f64 = 0.2;
+
+/// Upper bound of the concurrency chosen by `--concurrency auto`
+pub const AUTO_CONCURRENCY_MAX: usize = 32;
+
+/// `--concurrency auto` allows one request in flight per this many requests
+/// left in the quota
+const AUTO_QUOTA_PER_REQUEST: usize = 50;
+
+/// Pick a concurrency for `--concurrency auto`: four requests per available
+/// CPU, at most [`AUTO_CONCURRENCY_MAX`], no more than the fork pages to fetch
+/// when those are known, and lower still when little of the quota remains
+pub fn auto_concurrency(remaining: Option<usize>, pages: Option<usize>) -> usize {
+    let cpus = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut concurrency = (cpus * 4).clamp(ADAPTIVE_START, AUTO_CONCURRENCY_MAX);
+    if let Some(pages) = pages.filter(|&pages| pages > 0) {
+        concurrency = concurrency.min(pages);
+    }
+    if let Some(remaining) = remaining {
+        concurrency = concurrency.min(remaining / AUTO_QUOTA_PER_REQUEST);
+    }
+    concurrency.max(1)
+}
+
+/// Request permits whose number follows the rate-limit headers of the
+/// responses: one more while the quota stays healthy, half as many when it
+/// runs low, between 1 and the `--concurrency` ceiling
+#[derive(Clone, Debug)]
+pub struct AdaptiveConcurrency {
+    permits: Arc<Semaphore>,
+    state: Arc<Mutex<AdaptiveState>>,
+}
+
+#[derive(Debug)]
+struct AdaptiveState {
+    limit: usize,
+    max: usize,
+    /// Permits to retire once requests holding them finish
+    debt: usize,
+}
+
+impl AdaptiveConcurrency {
+    /// Start conservatively, growing to at most `max` requests in flight
+    pub fn new(max: usize) -> Self {
+        let max = max.max(1);
+        let limit = ADAPTIVE_START.min(max);
+        Self {
+            permits: Arc::new(Semaphore::new(limit)),
+            state: Arc::new(Mutex::new(AdaptiveState {
+                limit,
+                max,
+                debt: 0,
+            })),
+        }
+    }
+
+    /// The permit pool to hand to [`crate::FetchOptions::permits`]
+    pub fn permits(&self) -> Arc<Semaphore> {
+        self.permits.clone()
+    }
+
+    /// Adjust the number of permits from a response's `x-ratelimit-remaining`
+    /// and `x-ratelimit-limit` headers; responses without them are ignored
+    pub fn observe(&self, headers: &HeaderMap) {
+        let header_u64 = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.trim().parse::<u64>().ok())
+        };
+        let (Some(remaining), Some(quota)) = (
+            header_u64("x-ratelimit-remaining"),
+            header_u64("x-ratelimit-limit"),
+        ) else {
+            return;
+        };
+        let share = remaining as f64 / quota.max(1) as f64;
+
+        let mut state = self.state.lock().unwrap();
+        if state.debt > 0 {
+            state.debt -= self.permits.forget_permits(state.debt);
+        }
+        if share >= HEALTHY_QUOTA && state.limit < state.max {
+            state.limit += 1;
+            if state.debt > 0 {
+                state.debt -= 1;
+            } else {
+                self.permits.add_permits(1);
+            }
+            debug!("Raised concurrency to {}", state.limit);
+        } else if share < LOW_QUOTA && state.limit > 1 {
+            let reduced = state.limit / 2;
+            let excess = state.limit - reduced;
+            state.limit = reduced;
+            state.debt += excess - self.permits.forget_permits(excess);
+            info!(
+                "Only {} of {} API requests remain; lowering concurrency to {}",
+                remaining, quota, reduced
+            );
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use chrono::Utc;
+use octocrab::models::Repository;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tokio::time::Duration;
+use tracing::debug;
+
+use crate::{ForkliftError, MAX_PER_PAGE};
+
+/// Default directory for cached fork pages
+pub const DEFAULT_CACHE_DIR: &str = ".forklift-cache";
+
+/// A fork page as stored on disk
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CachedPage {
+    /// Unix timestamp (seconds) of when the page was fetched
+    pub fetched_at: i64,
+    /// Total number of pages, recorded on the first page only
+    pub total_pages: Option<u32>,
+    pub items: Vec<Repository>,
+}
+
+/// On-disk cache of fetched fork pages, keyed by `owner/repo/page`
+#[derive(Clone, Debug)]
+pub struct PageCache {
+    dir: PathBuf,
+    /// Entries older than this are ignored; `None` means they never expire
+    ttl: Option<Duration>,
+    /// Page size of the cached pages, kept apart per size as page numbers depend on it
+    per_page: u32,
+}
+
+impl PageCache {
+    pub fn new(dir: impl Into<PathBuf>, ttl: Option<Duration>) -> Self {
+        Self {
+            dir: dir.into(),
+            ttl,
+            per_page: MAX_PER_PAGE,
+        }
+    }
+
+    /// Cache pages of `per_page` forks, separately from other page sizes
+    pub fn with_per_page(mut self, per_page: u32) -> Self {
+        self.per_page = per_page;
+        self
+    }
+
+    fn page_path(&self, owner: &str, repo: &str, page: u32) -> PathBuf {
+        let dir = self.dir.join(owner).join(repo);
+        // Full pages keep the layout caches had before page sizes were configurable
+        let dir = if self.per_page == MAX_PER_PAGE {
+            dir
+        } else {
+            dir.join(format!("per-page-{}", self.per_page))
+        };
+        dir.join(format!("page-{}.json", page))
+    }
+
+    /// Load a cached page. Missing, unreadable, or (unless `ignore_ttl`)
+    /// expired entries are treated as a cache miss.
+    pub async fn load(
+        &self,
+        owner: &str,
+        repo: &str,
+        page: u32,
+        ignore_ttl: bool,
+    ) -> Option<CachedPage> {
+        let path = self.page_path(owner, repo, page);
+        let bytes = tokio::fs::read(&path).await.ok()?;
+
+        let cached: CachedPage = match serde_json::from_slice(&bytes) {
+            Ok(cached) => cached,
+            Err(e) => {
+                debug!("Ignoring unreadable cache entry {}: {}", path.display(), e);
+                return None;
+            }
+        };
+
+        if let (Some(ttl), false) = (self.ttl, ignore_ttl) {
+            let age = Utc::now().timestamp().saturating_sub(cached.fetched_at);
+            if age < 0 || age as u64 > ttl.as_secs() {
+                debug!("Cache entry {} expired ({}s old)", path.display(), age);
+                return None;
+            }
+        }
+
+        debug!("Serving page {} of {}/{} from cache", page, owner, repo);
+        Some(cached)
+    }
+
+    /// Store a freshly fetched page
+    pub async fn store(
+        &self,
+        owner: &str,
+        repo: &str,
+        page: u32,
+        total_pages: Option<u32>,
+        items: &[Repository],
+    ) -> Result<(), ForkliftError> {
+        let path = self.page_path(owner, repo, page);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        let entry = CachedPageRef {
+            fetched_at: Utc::now().timestamp(),
+            total_pages,
+            items,
+        };
+        tokio::fs::write(&path, serde_json::to_vec(&entry)?).await?;
+        Ok(())
+    }
+}
+
+/// Borrowed form of [`CachedPage`] so storing doesn't clone every item
+#[derive(Serialize)]
+struct CachedPageRef<'a> {
+    fetched_at: i64,
+    total_pages: Option<u32>,
+    items: &'a [Repository],
+}
@@ -0,0 +1,151 @@
+use octocrab::models::Repository;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+use crate::{ForkliftError, MAX_PER_PAGE};
+
+/// Extension of checkpoint files, which hold JSON
+pub const CHECKPOINT_SUFFIX: &str = ".partial";
+
+/// The forks fetched so far for one repository, as saved in a checkpoint
+#[derive(Debug, Default, Deserialize)]
+pub struct Checkpoint {
+    pub owner: String,
+    pub repo: String,
+    /// Page size the pages were fetched with, as page numbers depend on it
+    #[serde(default = "default_per_page")]
+    pub per_page: u32,
+    /// Fork pages whose forks are all in `forks`
+    pub pages: BTreeSet<u32>,
+    pub forks: Vec<Repository>,
+}
+
+fn default_per_page() -> u32 {
+    MAX_PER_PAGE
+}
+
+/// Where and how often the forks fetched so far are saved, so an interrupted
+/// run can be resumed with `--resume` instead of starting over
+#[derive(Clone, Debug)]
+pub struct CheckpointFile {
+    path: PathBuf,
+    /// Save after this many newly fetched pages
+    every: u32,
+}
+
+impl CheckpointFile {
+    pub fn new(path: impl Into<PathBuf>, every: u32) -> Self {
+        Self {
+            path: path.into(),
+            every: every.max(1),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn every(&self) -> u32 {
+        self.every
+    }
+
+    /// Read the checkpoint of `owner/repo`, if one was saved. A checkpoint
+    /// left by a different repository, or fetched with another page size than
+    /// `per_page`, is ignored with a warning.
+    pub fn load(
+        &self,
+        owner: &str,
+        repo: &str,
+        per_page: u32,
+    ) -> Result<Option<Checkpoint>, ForkliftError> {
+        let invalid = |message: String| ForkliftError::InvalidCheckpoint {
+            path: self.path.display().to_string(),
+            message,
+        };
+        let contents = match std::fs::read(&self.path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(invalid(e.to_string())),
+        };
+        let checkpoint: Checkpoint =
+            serde_json::from_slice(&contents).map_err(|e| invalid(e.to_string()))?;
+
+        if !checkpoint.owner.eq_ignore_ascii_case(owner)
+            || !checkpoint.repo.eq_ignore_ascii_case(repo)
+        {
+            warn!(
+                "Ignoring checkpoint {}: it belongs to {}/{}, not {}/{}",
+                self.path.display(),
+                checkpoint.owner,
+                checkpoint.repo,
+                owner,
+                repo
+            );
+            return Ok(None);
+        }
+        if checkpoint.per_page != per_page {
+            warn!(
+                "Ignoring checkpoint {}: it was fetched with --per-page {}, not {}",
+                self.path.display(),
+                checkpoint.per_page,
+                per_page
+            );
+            return Ok(None);
+        }
+        Ok(Some(checkpoint))
+    }
+
+    /// Replace the checkpoint with the forks of `pages`, writing a temporary
+    /// file first so a crash mid-write leaves the previous one intact
+    pub fn save(
+        &self,
+        owner: &str,
+        repo: &str,
+        per_page: u32,
+        pages: &BTreeSet<u32>,
+        forks: &[Repository],
+    ) -> Result<(), ForkliftError> {
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let checkpoint = CheckpointRef {
+            owner,
+            repo,
+            per_page,
+            pages,
+            forks,
+        };
+        let mut temp = self.path.clone().into_os_string();
+        temp.push(".tmp");
+        std::fs::write(&temp, serde_json::to_vec(&checkpoint)?)?;
+        std::fs::rename(&temp, &self.path)?;
+        debug!(
+            "Checkpointed {} forks from {} pages to {}",
+            forks.len(),
+            pages.len(),
+            self.path.display()
+        );
+        Ok(())
+    }
+
+    /// Delete the checkpoint once a fetch has completed
+    pub fn remove(&self) {
+        match std::fs::remove_file(&self.path) {
+            Ok(()) => debug!("Removed checkpoint {}", self.path.display()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => warn!("Could not remove checkpoint {}: {}", self.path.display(), e),
+        }
+    }
+}
+
+/// Borrowed form of [`Checkpoint`] so saving doesn't clone every fork
+#[derive(Serialize)]
+struct CheckpointRef<'a> {
+    owner: &'a str,
+    repo: &'a str,
+    per_page: u32,
+    pages: &'a BTreeSet<u32>,
+    forks: &'a [Repository],
+}
@@ -0,0 +1,293 @@
+use http::header::USER_AGENT;
+use http::{HeaderValue, Uri};
+use hyper_rustls::HttpsConnectorBuilder;
+use hyper_util::client::legacy::connect::proxy::Tunnel;
+use hyper_util::client::legacy::connect::HttpConnector;
+use hyper_util::client::legacy::Client;
+use hyper_util::client::proxy::matcher::Matcher;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use jsonwebtoken::EncodingKey;
+use octocrab::auth::AppAuth;
+use octocrab::models::{AppId, InstallationId, Rate};
+use octocrab::service::middleware::{
+    auth_header::AuthHeaderLayer, base_uri::BaseUriLayer, extra_headers::ExtraHeadersLayer,
+};
+use octocrab::{AuthState, Octocrab, OctocrabBuilder};
+use secrecy::ExposeSecret;
+use std::future::{poll_fn, Future};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::net::TcpStream;
+use tokio::time::Duration;
+use tower::{Layer, Service};
+use tower_http::follow_redirect::FollowRedirectLayer;
+use tracing::debug;
+
+use crate::ForkliftError;
+
+const GITHUB_API_URI: &str = "https://api.github.com";
+const GITHUB_UPLOAD_URI: &str = "https://uploads.github.com";
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Settings for the Octocrab client shared by every request
+#[derive(Clone, Debug, Default)]
+pub struct ClientOptions {
+    pub token: Option<String>,
+    /// GitHub Enterprise API base; `None` means api.github.com
+    pub api_base: Option<String>,
+    /// Connect/read/write timeout; `None` disables it
+    pub timeout: Option<Duration>,
+    /// Proxy URL overriding `HTTPS_PROXY`/`HTTP_PROXY`/`ALL_PROXY`
+    pub proxy: Option<String>,
+    /// `User-Agent` sent with every request; `None` means [`DEFAULT_USER_AGENT`]
+    pub user_agent: Option<String>,
+}
+
+/// Default `User-Agent`, identifying the tool and version in GitHub's logs
+pub const DEFAULT_USER_AGENT: &str = concat!("forklift/", env!("CARGO_PKG_VERSION"));
+
+/// GitHub App credentials for minting an installation token
+#[derive(Clone, Debug)]
+pub struct GithubApp {
+    pub app_id: u64,
+    pub installation_id: u64,
+    /// Path to the app's PEM-encoded RSA private key
+    pub private_key_path: String,
+}
+
+/// Build an Octocrab client, routing it through an HTTP proxy when `--proxy`
+/// or the proxy environment variables apply to the API host.
+///
+/// This is the stack Octocrab builds by default, assembled by hand so the
+/// connector can tunnel through a proxy and the `User-Agent` can be set
+/// (Octocrab's own builder always sends `octocrab`).
+pub fn build_client(options: &ClientOptions) -> Result<Octocrab, ForkliftError> {
+    build_client_with_auth(options, AuthState::None)
+}
+
+/// Mint an installation access token for `app` through Octocrab's app auth,
+/// sending the request over the same proxy/`User-Agent` stack as
+/// [`build_client`]. Installation tokens expire after an hour.
+pub async fn mint_installation_token(
+    options: &ClientOptions,
+    app: &GithubApp,
+) -> Result<String, ForkliftError> {
+    let invalid = |message: String| ForkliftError::InvalidAppCredentials(message);
+    let pem = std::fs::read(&app.private_key_path)
+        .map_err(|e| invalid(format!("reading {}: {}", app.private_key_path, e)))?;
+    let key = EncodingKey::from_rsa_pem(&pem).map_err(|e| {
+        invalid(format!(
+            "{} is not an RSA private key in PEM format: {}",
+            app.private_key_path, e
+        ))
+    })?;
+
+    let app_client = build_client_with_auth(
+        &ClientOptions {
+            token: None,
+            ..options.clone()
+        },
+        AuthState::App(AppAuth {
+            app_id: AppId(app.app_id),
+            key,
+        }),
+    )?;
+    match app_client
+        .installation_and_token(InstallationId(app.installation_id))
+        .await
+    {
+        Ok((_, token)) => Ok(token.expose_secret().to_string()),
+        Err(octocrab::Error::GitHub { source, .. }) => Err(invalid(format!(
+            "GitHub refused an installation token for app {} installation {}: {}",
+            app.app_id, app.installation_id, source.message
+        ))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn build_client_with_auth(
+    options: &ClientOptions,
+    auth: AuthState,
+) -> Result<Octocrab, ForkliftError> {
+    let base_uri: Uri = options
+        .api_base
+        .as_deref()
+        .unwrap_or(GITHUB_API_URI)
+        .parse()
+        .map_err(|e: http::uri::InvalidUri| ForkliftError::InvalidUrl(e.to_string()))?;
+
+    let user_agent = options.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT);
+    let user_agent = HeaderValue::from_str(user_agent)
+        .map_err(|_| ForkliftError::InvalidUserAgent(user_agent.to_string()))?;
+
+    let builder = Octocrab::builder()
+        .set_connect_timeout(options.timeout)
+        .set_read_timeout(options.timeout)
+        .set_write_timeout(options.timeout);
+
+    let matcher = proxy_matcher(options.proxy.as_deref())?;
+    if let Some(intercept) = matcher.intercept(&base_uri) {
+        debug!("Routing API requests through proxy {}", intercept.uri());
+    }
+
+    let https = HttpsConnectorBuilder::new()
+        .with_native_roots()?
+        .https_or_http()
+        .enable_http1()
+        .wrap_connector(ProxyConnector::new(matcher));
+    let connector = builder.set_connect_timeout_service(https);
+    let client =
+        builder.set_connector_retry_service(Client::builder(TokioExecutor::new()).build(connector));
+    let client = FollowRedirectLayer::new().layer(client);
+
+    let headers = vec![(USER_AGENT, user_agent)];
+    let auth_header = options
+        .token
+        .as_ref()
+        .map(|token| {
+            HeaderValue::from_str(&format!("Bearer {}", token))
+                .map_err(|_| ForkliftError::MalformedToken)
+        })
+        .transpose()?;
+    let upload_uri = if options.api_base.is_some() {
+        base_uri.clone()
+    } else {
+        Uri::from_static(GITHUB_UPLOAD_URI)
+    };
+
+    let octocrab = OctocrabBuilder::new_empty()
+        .with_service(client)
+        .with_layer(&ExtraHeadersLayer::new(Arc::new(headers)))
+        .with_layer(&BaseUriLayer::new(base_uri.clone()))
+        .with_layer(&AuthHeaderLayer::new(auth_header, base_uri, upload_uri))
+        .with_auth(auth)
+        .build()
+        .expect("building a custom Octocrab service is infallible");
+    Ok(octocrab)
+}
+
+/// Read a token from the first line of the file at `path`, trimming whitespace
+pub fn read_token_file(path: &str) -> Result<String, ForkliftError> {
+    let contents =
+        std::fs::read_to_string(path).map_err(|e| ForkliftError::UnreadableTokenFile {
+            path: path.to_string(),
+            message: e.to_string(),
+        })?;
+    let token = contents.lines().next().unwrap_or_default().trim();
+    if token.is_empty() {
+        return Err(ForkliftError::EmptyTokenFile(path.to_string()));
+    }
+    Ok(token.to_string())
+}
+
+/// Make one cheap request so a bad token fails before any fetching starts,
+/// returning the core REST quota of the token (or of anonymous access). `/rate_limit`
+/// works for every token type and doesn't count against the quota. Only a
+/// rejected token is reported as [`ForkliftError::InvalidToken`]; callers can
+/// treat any other error as the token merely lacking access to `/rate_limit`.
+pub async fn validate_token(octocrab: &Octocrab) -> Result<Rate, ForkliftError> {
+    match octocrab.ratelimit().get().await {
+        Ok(limits) => Ok(limits.resources.core),
+        Err(octocrab::Error::GitHub { source, .. })
+            if source.status_code == http::StatusCode::UNAUTHORIZED =>
+        {
+            Err(ForkliftError::InvalidToken)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// A client builder for requests outside the GitHub API (`--post-to`, S3
+/// uploads) with the same timeout, `User-Agent`, and proxy as the API client
+pub(crate) fn http_client_builder(
+    options: &ClientOptions,
+) -> Result<reqwest::ClientBuilder, ForkliftError> {
+    let mut builder = reqwest::Client::builder()
+        .user_agent(options.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT));
+    if let Some(timeout) = options.timeout {
+        builder = builder.timeout(timeout);
+    }
+    // Without --proxy, reqwest reads the same proxy environment variables
+    if let Some(proxy) = options.proxy.as_deref() {
+        check_proxy(proxy)?;
+        let proxy = reqwest::Proxy::all(proxy)
+            .map_err(|_| ForkliftError::InvalidProxy(proxy.into()))?
+            .no_proxy(reqwest::NoProxy::from_env());
+        builder = builder.proxy(proxy);
+    }
+    Ok(builder)
+}
+
+/// Only plain `http://` proxies with a host are supported
+fn check_proxy(proxy: &str) -> Result<(), ForkliftError> {
+    let parsed = url::Url::parse(proxy).map_err(|_| ForkliftError::InvalidProxy(proxy.into()))?;
+    if parsed.scheme() != "http" || parsed.host_str().is_none() {
+        return Err(ForkliftError::InvalidProxy(proxy.into()));
+    }
+    Ok(())
+}
+
+/// Proxy rules from an explicit URL (still honoring `NO_PROXY`) or the environment
+fn proxy_matcher(proxy: Option<&str>) -> Result<Matcher, ForkliftError> {
+    let Some(proxy) = proxy else {
+        return Ok(Matcher::from_env());
+    };
+    check_proxy(proxy)?;
+    let no_proxy = std::env::var("NO_PROXY")
+        .or_else(|_| std::env::var("no_proxy"))
+        .unwrap_or_default();
+    Ok(Matcher::builder()
+        .all(proxy.to_string())
+        .no(no_proxy)
+        .build())
+}
+
+/// TCP connector that opens an HTTP CONNECT tunnel through the matching
+/// proxy, or connects directly when no proxy applies to the destination
+#[derive(Clone)]
+struct ProxyConnector {
+    http: HttpConnector,
+    matcher: Arc<Matcher>,
+}
+
+impl ProxyConnector {
+    fn new(matcher: Matcher) -> Self {
+        let mut http = HttpConnector::new();
+        // TLS is layered on top by the HTTPS connector
+        http.enforce_http(false);
+        Self {
+            http,
+            matcher: Arc::new(matcher),
+        }
+    }
+}
+
+impl Service<Uri> for ProxyConnector {
+    type Response = TokioIo<TcpStream>;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.http.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, dst: Uri) -> Self::Future {
+        let mut http = self.http.clone();
+        let intercept = self.matcher.intercept(&dst);
+        Box::pin(async move {
+            match intercept {
+                Some(intercept) => {
+                    let mut tunnel = Tunnel::new(intercept.uri().clone(), http);
+                    if let Some(auth) = intercept.basic_auth() {
+                        tunnel = tunnel.with_auth(auth.clone());
+                    }
+                    poll_fn(|cx| tunnel.poll_ready(cx)).await?;
+                    Ok(tunnel.call(dst).await?)
+                }
+                None => Ok(http.call(dst).await?),
+            }
+        })
+    }
+}
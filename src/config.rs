@@ -0,0 +1,53 @@
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+use crate::{ForkliftError, OutputFormat, SortKey};
+
+/// Config file picked up from the working directory when `--config` isn't given
+pub const DEFAULT_CONFIG_FILE: &str = "forklift.toml";
+
+/// Defaults read from a `forklift.toml`. Keys are named after the CLI flags
+/// (`min-stars = 5`), and any flag given on the command line wins.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    pub token_file: Option<String>,
+    pub allow_unauthenticated: Option<bool>,
+    pub output_dir: Option<PathBuf>,
+    pub base_url: Option<String>,
+    pub proxy: Option<String>,
+    pub format: Option<OutputFormat>,
+    pub sort: Option<SortKey>,
+    pub concurrency: Option<usize>,
+    pub adaptive: Option<bool>,
+    pub timeout: Option<u64>,
+    pub max_retries: Option<u32>,
+    pub retry_base_delay: Option<u64>,
+    pub min_stars: Option<u32>,
+    pub active_since: Option<String>,
+    pub include_users: Option<bool>,
+    pub include_private: Option<bool>,
+    pub exclude_archived: Option<bool>,
+    pub org_allow: Option<Vec<String>>,
+    pub org_deny: Option<Vec<String>>,
+    pub filter_name: Option<String>,
+}
+
+/// Load the config file at `path`, or [`DEFAULT_CONFIG_FILE`] if it exists
+/// when no path is given. An explicit path must exist.
+pub fn load_config(path: Option<&str>) -> Result<Config, ForkliftError> {
+    let path = match path {
+        Some(path) => Path::new(path),
+        None if Path::new(DEFAULT_CONFIG_FILE).is_file() => Path::new(DEFAULT_CONFIG_FILE),
+        None => return Ok(Config::default()),
+    };
+    debug!("Reading defaults from {}", path.display());
+
+    let invalid = |message: String| ForkliftError::InvalidConfig {
+        path: path.display().to_string(),
+        message,
+    };
+    let contents = std::fs::read_to_string(path).map_err(|e| invalid(e.to_string()))?;
+    toml::from_str(&contents).map_err(|e| invalid(e.message().to_string()))
+}
@@ -0,0 +1,224 @@
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+
+use crate::{ForkRecord, ForkliftError};
+
+/// A fork read back from an earlier `--format json` report. Every key is
+/// optional so reports written with `--fields` can still be compared.
+#[derive(Clone, Debug, Deserialize)]
+pub struct PreviousFork {
+    pub id: Option<u64>,
+    pub organization: Option<String>,
+    pub fork_name: Option<String>,
+    pub url: Option<String>,
+    pub stars: Option<u32>,
+    pub archived: Option<bool>,
+}
+
+impl PreviousFork {
+    /// `owner/name`, or whatever identifies the fork when those keys are missing
+    fn full_name(&self) -> String {
+        match (&self.organization, &self.fork_name) {
+            (Some(org), Some(name)) => format!("{}/{}", org, name),
+            _ => self
+                .url
+                .clone()
+                .or_else(|| self.id.map(|id| format!("#{}", id)))
+                .unwrap_or_else(|| "(unknown fork)".to_string()),
+        }
+    }
+}
+
+/// Earlier forks indexed by the keys a current fork is matched on: the
+/// repository id when both reports have one, which survives renames, and
+/// otherwise the URL or, lacking that, `owner/name` (both ignoring case)
+#[derive(Default)]
+struct PreviousIndex {
+    by_id: HashMap<u64, Vec<usize>>,
+    by_url: HashMap<String, Vec<usize>>,
+    by_name: HashMap<String, Vec<usize>>,
+}
+
+impl PreviousIndex {
+    fn new(previous: &[PreviousFork]) -> Self {
+        let mut index = Self::default();
+        for (i, before) in previous.iter().enumerate() {
+            if let Some(id) = before.id {
+                index.by_id.entry(id).or_default().push(i);
+            }
+            if let Some(url) = &before.url {
+                index
+                    .by_url
+                    .entry(url.to_ascii_lowercase())
+                    .or_default()
+                    .push(i);
+            } else if let (Some(org), Some(name)) = (&before.organization, &before.fork_name) {
+                let key = format!("{}/{}", org, name).to_ascii_lowercase();
+                index.by_name.entry(key).or_default().push(i);
+            }
+        }
+        index
+    }
+
+    /// The first earlier fork not yet `matched` that is `fork`
+    fn find(
+        &self,
+        previous: &[PreviousFork],
+        fork: &ForkRecord,
+        matched: &HashSet<usize>,
+    ) -> Option<usize> {
+        let by_id = fork.id.and_then(|id| self.by_id.get(&id));
+        let name = format!("{}/{}", fork.organization, fork.fork_name).to_ascii_lowercase();
+        // Forks with an id on both sides are only ever matched by it
+        let by_key = [
+            self.by_url.get(&fork.url.to_ascii_lowercase()),
+            self.by_name.get(&name),
+        ]
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|&&i| fork.id.is_none() || previous[i].id.is_none());
+        by_id
+            .into_iter()
+            .flatten()
+            .chain(by_key)
+            .copied()
+            .find(|i| !matched.contains(i))
+    }
+}
+
+/// How a fork listed in both reports changed
+#[derive(Clone, Debug)]
+pub struct ForkChange {
+    /// `owner/name` now
+    pub full_name: String,
+    /// `owner/name` in the previous report, when the fork was renamed or transferred
+    pub renamed_from: Option<String>,
+    /// Stars before and now, when they differ
+    pub stars: Option<(u32, u32)>,
+    /// Whether the fork is archived now, when that changed
+    pub archived: Option<bool>,
+}
+
+/// Forks new, removed, or changed since an earlier report
+#[derive(Clone, Debug, Default)]
+pub struct ForkDiff {
+    /// Path of the earlier report
+    pub previous: String,
+    /// `owner/name` and stars of forks that weren't in the earlier report
+    pub added: Vec<(String, u32)>,
+    /// `owner/name` and stars of forks that are no longer listed
+    pub removed: Vec<(String, Option<u32>)>,
+    pub changed: Vec<ForkChange>,
+}
+
+/// Read the forks of a JSON report written by an earlier run
+pub fn load_previous_report(path: &str) -> Result<Vec<PreviousFork>, ForkliftError> {
+    let invalid = |message: String| ForkliftError::InvalidPreviousReport {
+        path: path.to_string(),
+        message,
+    };
+    let contents = std::fs::read(path).map_err(|e| invalid(e.to_string()))?;
+    serde_json::from_slice(&contents).map_err(|e| {
+        invalid(format!(
+            "{} (expected a report written with --format json)",
+            e
+        ))
+    })
+}
+
+/// Compare the current `forks` with those of the report at `previous_path`
+pub fn diff_forks(
+    previous_path: &str,
+    previous: &[PreviousFork],
+    forks: &[ForkRecord],
+) -> ForkDiff {
+    let mut diff = ForkDiff {
+        previous: previous_path.to_string(),
+        ..ForkDiff::default()
+    };
+    let index = PreviousIndex::new(previous);
+    let mut matched = HashSet::new();
+
+    for fork in forks {
+        let full_name = format!("{}/{}", fork.organization, fork.fork_name);
+        let Some(index) = index.find(previous, fork, &matched) else {
+            diff.added.push((full_name, fork.stars));
+            continue;
+        };
+        matched.insert(index);
+
+        let before = &previous[index];
+        let previous_name = before.full_name();
+        let change = ForkChange {
+            renamed_from: (before.organization.is_some()
+                && before.fork_name.is_some()
+                && !previous_name.eq_ignore_ascii_case(&full_name))
+            .then_some(previous_name),
+            stars: before
+                .stars
+                .filter(|&stars| stars != fork.stars)
+                .map(|stars| (stars, fork.stars)),
+            archived: before
+                .archived
+                .filter(|&archived| archived != fork.archived)
+                .map(|_| fork.archived),
+            full_name,
+        };
+        if change.renamed_from.is_some() || change.stars.is_some() || change.archived.is_some() {
+            diff.changed.push(change);
+        }
+    }
+
+    diff.removed = previous
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !matched.contains(i))
+        .map(|(_, before)| (before.full_name(), before.stars))
+        .collect();
+    diff
+}
+
+impl ForkDiff {
+    /// One-line count of the changes, e.g. `2 new, 1 removed, 3 changed`
+    pub fn counts(&self) -> String {
+        format!(
+            "{} new, {} removed, {} changed",
+            self.added.len(),
+            self.removed.len(),
+            self.changed.len()
+        )
+    }
+
+    /// The changes as `diff`-style lines: `+` new, `-` removed, `~` changed
+    pub fn lines(&self) -> Vec<String> {
+        let stars = |n: u32| if n == 1 { "star" } else { "stars" };
+        let mut lines = Vec::new();
+        for (name, count) in &self.added {
+            lines.push(format!("+ {} ({} {})", name, count, stars(*count)));
+        }
+        for (name, count) in &self.removed {
+            lines.push(match count {
+                Some(count) => format!("- {} ({} {})", name, count, stars(*count)),
+                None => format!("- {}", name),
+            });
+        }
+        for change in &self.changed {
+            let mut details = Vec::new();
+            if let Some(from) = &change.renamed_from {
+                details.push(format!("renamed from {}", from));
+            }
+            if let Some((before, after)) = change.stars {
+                let delta = i64::from(after) - i64::from(before);
+                details.push(format!("stars {} → {} ({:+})", before, after, delta));
+            }
+            match change.archived {
+                Some(true) => details.push("archived".to_string()),
+                Some(false) => details.push("unarchived".to_string()),
+                None => {}
+            }
+            lines.push(format!("~ {}: {}", change.full_name, details.join(", ")));
+        }
+        lines
+    }
+}
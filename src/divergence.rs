@@ -0,0 +1,94 @@
+use octocrab::Octocrab;
+use serde::Deserialize;
+use std::sync::Arc;
+use tokio::{sync::Semaphore, task::JoinSet};
+use tracing::{debug, info, warn};
+
+use crate::s3::encode_path;
+use crate::{ForkRecord, ForkliftError};
+
+/// The subset of GitHub's compare response we care about
+#[derive(Debug, Deserialize)]
+struct Comparison {
+    ahead_by: u64,
+    behind_by: u64,
+}
+
+/// Look up the default branch of the upstream repository
+pub async fn upstream_default_branch(
+    octocrab: &Octocrab,
+    owner: &str,
+    repo: &str,
+) -> Result<String, ForkliftError> {
+    let upstream = octocrab.repos(owner, repo).get().await?;
+    Ok(upstream
+        .default_branch
+        .unwrap_or_else(|| "main".to_string()))
+}
+
+/// Fill in ahead/behind commit counts for every fork by comparing
+/// `base_branch` on upstream with each fork's own default branch (or the
+/// same branch, when the fork's is unknown), holding one of `permits` per
+/// comparison. Forks whose comparison fails (e.g. an empty
+/// repository) are left without counts and render as `-`.
+pub async fn compute_divergence(
+    octocrab: &Octocrab,
+    owner: &str,
+    repo: &str,
+    base_branch: &str,
+    forks: &mut [ForkRecord],
+    permits: &Arc<Semaphore>,
+) -> Result<(), ForkliftError> {
+    info!(
+        "Comparing {} forks against {}/{}@{}",
+        forks.len(),
+        owner,
+        repo,
+        base_branch
+    );
+
+    let mut tasks = JoinSet::new();
+
+    for (index, fork) in forks.iter().enumerate() {
+        let octo = octocrab.clone();
+        let head_branch = fork.default_branch.as_deref().unwrap_or(base_branch);
+        // Branch names may hold `#`, `?`, or `%`; their `/`s stay as GitHub expects
+        let route = format!(
+            "/repos/{}/{}/compare/{}...{}:{}",
+            owner,
+            repo,
+            encode_path(base_branch),
+            fork.organization,
+            encode_path(head_branch)
+        );
+        let label = format!("{}/{}", fork.organization, fork.fork_name);
+        let permit = permits.clone().acquire_owned().await.unwrap();
+
+        tasks.spawn(async move {
+            let _permit = permit;
+            let result: Result<Comparison, octocrab::Error> = octo.get(route, None::<&()>).await;
+            if let Err(e) = &result {
+                debug!("Comparison failed for {}: {}", label, e);
+            }
+            (index, result.ok())
+        });
+    }
+
+    let mut compared = 0;
+    while let Some(res) = tasks.join_next().await {
+        let (index, comparison) = res?;
+        if let Some(comparison) = comparison {
+            forks[index].ahead = Some(comparison.ahead_by);
+            forks[index].behind = Some(comparison.behind_by);
+            compared += 1;
+        }
+    }
+    if compared == 0 && !forks.is_empty() {
+        warn!(
+            "No fork could be compared against {}/{}@{}; check that the branch exists",
+            owner, repo, base_branch
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,240 @@
+use http::StatusCode;
+use thiserror::Error;
+
+use crate::fetch::rate_limit_kind;
+
+/// Process exit codes, one per class of failure, so scripts can tell them apart
+pub mod exit_code {
+    /// Any failure without a more specific code
+    pub const FAILURE: u8 = 1;
+    /// Invalid flags, config file, or other input (clap also exits with 2)
+    pub const USAGE: u8 = 2;
+    /// The `--timeout-total` budget ran out; partial reports were written
+    pub const TIMED_OUT: u8 = 3;
+    /// A repository URL couldn't be parsed
+    pub const INVALID_URL: u8 = 4;
+    /// No usable credentials: missing or rejected token, bad GitHub App setup
+    pub const AUTH: u8 = 5;
+    /// A repository doesn't exist or isn't visible to the token
+    pub const NOT_FOUND: u8 = 6;
+    /// GitHub kept rate limiting after every retry
+    pub const RATE_LIMITED: u8 = 7;
+    /// Reading or writing a local file failed
+    pub const IO: u8 = 8;
+    /// Any other GitHub API or network failure
+    pub const API: u8 = 9;
+    /// `--fail-if-empty` was set and a repository had no forks left after
+    /// filtering; its report was still written
+    pub const EMPTY: u8 = 10;
+    /// An `--exec` command failed for at least one fork
+    pub const EXEC_FAILED: u8 = 11;
+    /// An organization not listed in the `--expected` file forked the
+    /// repository; the report was still written
+    pub const UNEXPECTED_FORKS: u8 = 12;
+}
+
+#[derive(Debug, Error)]
+pub enum ForkliftError {
+    #[error("No GitHub token found. Please set GITHUB_TOKEN in .env or environment variable, or pass --token=<TOKEN> on CLI. Small public repositories can be analyzed without one via --allow-unauthenticated.")]
+    MissingGithubToken,
+
+    #[error("--post-to needs an http(s) URL, but got: {0}")]
+    InvalidPostUrl(String),
+
+    #[error("Invalid --post-header {0:?}; expected \"Name: value\"")]
+    InvalidPostHeader(String),
+
+    #[error("Failed to POST results to {url}: {message}")]
+    PostFailed { url: String, message: String },
+
+    #[error("--output {0} is not a valid S3 location; expected s3://BUCKET/KEY")]
+    InvalidS3Url(String),
+
+    #[error("AWS_ENDPOINT_URL_S3 or AWS_ENDPOINT_URL needs an http(s) URL, but got: {0}")]
+    InvalidS3Endpoint(String),
+
+    #[error("Writing to S3 needs AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY to be set")]
+    MissingS3Credentials,
+
+    #[error("--append can't extend a report in S3, since objects are replaced whole")]
+    AppendToS3,
+
+    #[error("Failed to upload the report to {location}: {message}")]
+    S3UploadFailed { location: String, message: String },
+
+    #[error("Can't read the previous report {path}: {message}")]
+    InvalidPreviousReport { path: String, message: String },
+
+    #[error(
+        "--diff compares against a single repository's report, but {0} repositories were given"
+    )]
+    DiffWithMultipleRepos(usize),
+
+    #[error("GitHub App authentication failed: {0}")]
+    InvalidAppCredentials(String),
+
+    #[error("--token-env names the environment variable {0}, but it is unset or empty")]
+    MissingTokenEnv(String),
+
+    #[error("Token file {0} is empty; put the token on its first line")]
+    EmptyTokenFile(String),
+
+    #[error("Can't read the token file {path}: {message}")]
+    UnreadableTokenFile { path: String, message: String },
+
+    #[error("GitHub rejected the token (401 Unauthorized); it may be expired, revoked, or mistyped. Generate a new token and pass it via GITHUB_TOKEN or --token.")]
+    InvalidToken,
+
+    #[error("The GitHub token contains characters that can't be sent in an HTTP header, such as a line break; check how it was copied")]
+    MalformedToken,
+
+    #[error("Failed to parse repository URL: {0}")]
+    InvalidUrl(String),
+
+    #[error("Expected a '{expected}' domain, but got: {found}")]
+    InvalidDomain { expected: String, found: String },
+
+    #[error("Expected the URL path format to be /OWNER/REPO, but got: {0:?}")]
+    InvalidPathSegments(Vec<String>),
+
+    #[error("Repository {owner}/{repo} was not found; check the URL for typos, or that your token can access it if it is private")]
+    RepoNotFound { owner: String, repo: String },
+
+    #[error("--output names a single file but {0} repositories were given; omit it to write one report per repository, or pass --append to combine them")]
+    OutputWithMultipleRepos(usize),
+
+    #[error("--append only works with Markdown, CSV, and NDJSON reports; JSON and HTML documents can't be concatenated")]
+    AppendUnsupported,
+
+    #[error("Several --format values write several reports, which can't all go to stdout; give --output a file name or omit it")]
+    MultipleFormatsToStdout,
+
+    #[error("--output {path} looks like a {found} report but --format {format} was chosen; rename the file or pass a matching --format")]
+    OutputExtensionMismatch {
+        path: String,
+        found: String,
+        format: String,
+    },
+
+    #[error("Invalid config file {path}: {message}")]
+    InvalidConfig { path: String, message: String },
+
+    #[error("--since-page {since} is after --until-page {until}")]
+    InvalidPageRange { since: u32, until: u32 },
+
+    #[error("Invalid date '{0}': expected YYYY-MM-DD, an RFC 3339 timestamp, or a relative age like 90d")]
+    InvalidDate(String),
+
+    #[error("Invalid ignore file {path}: {message}")]
+    InvalidIgnoreFile { path: String, message: String },
+
+    #[error("Can't open the --log-file {path}: {message}")]
+    InvalidLogFile { path: String, message: String },
+
+    #[error("Can't read the --expected file {path}: {message}")]
+    InvalidExpectedFile { path: String, message: String },
+
+    #[error("Invalid --filter-name pattern {pattern:?}: {message}")]
+    InvalidNamePattern { pattern: String, message: String },
+
+    #[error("GraphQL query failed: {0}")]
+    GraphqlError(String),
+
+    #[error("Invalid User-Agent {0:?}: it must be printable ASCII")]
+    InvalidUserAgent(String),
+
+    #[error("Invalid proxy URL: {0}")]
+    InvalidProxy(String),
+
+    #[error("Can't resume from checkpoint {path}: {message}")]
+    InvalidCheckpoint { path: String, message: String },
+
+    #[error("Page {page} of {owner}/{repo} is not cached; run without --offline to fetch it")]
+    CacheMiss {
+        owner: String,
+        repo: String,
+        page: u32,
+    },
+
+    #[error("GitHub API request failed: {}", describe_octocrab_error(.0))]
+    OctocrabError(#[from] octocrab::Error),
+
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+
+    #[error(transparent)]
+    JsonError(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    JoinError(#[from] tokio::task::JoinError),
+}
+
+impl ForkliftError {
+    /// The [`exit_code`] for this failure
+    pub fn exit_code(&self) -> u8 {
+        use ForkliftError::*;
+        match self {
+            InvalidPostUrl(_)
+            | InvalidPostHeader(_)
+            | InvalidS3Url(_)
+            | InvalidS3Endpoint(_)
+            | AppendToS3
+            | InvalidPreviousReport { .. }
+            | DiffWithMultipleRepos(_)
+            | OutputWithMultipleRepos(_)
+            | AppendUnsupported
+            | MultipleFormatsToStdout
+            | OutputExtensionMismatch { .. }
+            | InvalidConfig { .. }
+            | InvalidPageRange { .. }
+            | InvalidDate(_)
+            | InvalidIgnoreFile { .. }
+            | InvalidExpectedFile { .. }
+            | InvalidNamePattern { .. }
+            | InvalidUserAgent(_)
+            | InvalidProxy(_) => exit_code::USAGE,
+            InvalidUrl(_) | InvalidDomain { .. } | InvalidPathSegments(_) => exit_code::INVALID_URL,
+            MissingGithubToken
+            | MissingTokenEnv(_)
+            | EmptyTokenFile(_)
+            | UnreadableTokenFile { .. }
+            | InvalidToken
+            | MalformedToken
+            | InvalidAppCredentials(_)
+            | MissingS3Credentials => exit_code::AUTH,
+            RepoNotFound { .. } => exit_code::NOT_FOUND,
+            OctocrabError(e) if rate_limit_kind(e).is_some() => exit_code::RATE_LIMITED,
+            OctocrabError(octocrab::Error::GitHub { source, .. }) => match source.status_code {
+                StatusCode::UNAUTHORIZED => exit_code::AUTH,
+                StatusCode::NOT_FOUND => exit_code::NOT_FOUND,
+                _ => exit_code::API,
+            },
+            OctocrabError(_) | GraphqlError(_) | PostFailed { .. } | S3UploadFailed { .. } => {
+                exit_code::API
+            }
+            IoError(_) | CacheMiss { .. } | InvalidCheckpoint { .. } | InvalidLogFile { .. } => {
+                exit_code::IO
+            }
+            JsonError(_) | JoinError(_) => exit_code::FAILURE,
+        }
+    }
+}
+
+/// Octocrab's own message is often just "GitHub"; show GitHub's status and
+/// message instead, or the underlying cause for transport errors
+fn describe_octocrab_error(err: &octocrab::Error) -> String {
+    match err {
+        octocrab::Error::GitHub { source, .. } => {
+            format!("{} ({})", source.message, source.status_code)
+        }
+        _ => {
+            let mut message = err.to_string();
+            let mut source = std::error::Error::source(err);
+            while let Some(cause) = source {
+                message = format!("{}: {}", message, cause);
+                source = cause.source();
+            }
+            message
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use std::process::Stdio;
+use std::sync::Arc;
+use tokio::{process::Command, sync::Semaphore, task::JoinSet};
+use tracing::{debug, warn};
+
+use crate::{ForkRecord, ForkliftError};
+
+/// What running an `--exec` command over a repository's forks came to
+#[derive(Debug, Default)]
+pub struct ExecSummary {
+    /// Forks the command was run for
+    pub ran: usize,
+    /// `owner/name` of every fork whose command failed, with how it failed
+    pub failures: Vec<(String, String)>,
+}
+
+/// Run `command` through the shell once per fork, holding one of `permits`
+/// while it runs. The fork's owner, name, and URL are passed as the
+/// arguments `$1`, `$2`, and `$3` and as `FORKLIFT_ORG`, `FORKLIFT_FORK`, and
+/// `FORKLIFT_URL`. The command's output goes to stderr, so it never mixes
+/// with a report written to stdout.
+pub async fn exec_per_fork(
+    command: &str,
+    forks: &[ForkRecord],
+    permits: &Arc<Semaphore>,
+) -> Result<ExecSummary, ForkliftError> {
+    let mut tasks = JoinSet::new();
+    for fork in forks {
+        let mut child = shell(command);
+        child
+            .args([&fork.organization, &fork.fork_name, &fork.url])
+            .env("FORKLIFT_ORG", &fork.organization)
+            .env("FORKLIFT_FORK", &fork.fork_name)
+            .env("FORKLIFT_URL", &fork.url)
+            .stdin(Stdio::null())
+            .stdout(std::io::stderr())
+            .kill_on_drop(true);
+        let label = format!("{}/{}", fork.organization, fork.fork_name);
+        let permit = permits.clone().acquire_owned().await.unwrap();
+
+        tasks.spawn(async move {
+            let _permit = permit;
+            debug!("Running --exec for {}", label);
+            let failure = match child.status().await {
+                Ok(status) if status.success() => None,
+                Ok(status) => Some(match status.code() {
+                    Some(code) => format!("exited with code {}", code),
+                    None => "was killed by a signal".to_string(),
+                }),
+                Err(e) => Some(format!("could not be started: {}", e)),
+            };
+            (label, failure)
+        });
+    }
+
+    let mut summary = ExecSummary::default();
+    while let Some(res) = tasks.join_next().await {
+        let (label, failure) = res?;
+        summary.ran += 1;
+        if let Some(failure) = failure {
+            warn!("--exec command for {} {}", label, failure);
+            summary.failures.push((label, failure));
+        }
+    }
+    summary.failures.sort();
+    Ok(summary)
+}
+
+/// `command` run by the platform's shell, with any further arguments
+/// available to it as positional parameters
+fn shell(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C").arg(command);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        // The first argument after the script becomes `$0`
+        shell.arg("-c").arg(command).arg("forklift");
+        shell
+    }
+}
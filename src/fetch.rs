@@ -0,0 +1,1081 @@
+use chrono::Utc;
+use http::{HeaderMap, StatusCode, Uri};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use octocrab::models::{Repository, RepositoryId};
+use octocrab::{FromResponse, Octocrab, Page};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashSet};
+use std::fs::File;
+use std::future::Future;
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::{
+    sync::{mpsc::UnboundedSender, Semaphore},
+    task::{JoinError, JoinSet},
+    time::{sleep, timeout_at, Duration, Instant},
+};
+use tracing::{debug, error, info, warn};
+
+use crate::{AdaptiveConcurrency, CheckpointFile, ForkliftError, PageCache};
+
+/// Log at `level`, or only at debug level when the fetch is `quiet`
+macro_rules! unless_quiet {
+    ($quiet:expr, $level:ident, $($arg:tt)+) => {
+        if $quiet {
+            debug!($($arg)+)
+        } else {
+            $level!($($arg)+)
+        }
+    };
+}
+
+/// Most forks GitHub returns per page, and the default page size
+pub const MAX_PER_PAGE: u32 = 100;
+
+/// Retry behaviour for rate-limited page fetches
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Exponential backoff for the given (1-based) attempt: base, 2*base, 4*base, ...
+    /// randomized by up to 50% either way so concurrent tasks that hit the
+    /// limit together don't all retry at the same instant
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let wait = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)));
+        wait.mul_f64(rand::random_range(0.5..1.5))
+    }
+
+    /// Backoff after a secondary rate limit without server advice: GitHub asks
+    /// for at least a minute between retries, doubling per attempt and
+    /// randomized upwards only, so the minute is always respected
+    pub fn secondary_backoff(&self, attempt: u32) -> Duration {
+        let wait = SECONDARY_RATE_LIMIT_WAIT
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)));
+        wait.mul_f64(rand::random_range(1.0..1.5))
+    }
+
+    /// Backoff for the given attempt after hitting `limit`
+    pub(crate) fn rate_limit_backoff(&self, limit: RateLimit, attempt: u32) -> Duration {
+        match limit {
+            RateLimit::Primary => self.backoff(attempt),
+            RateLimit::Secondary => self.secondary_backoff(attempt),
+        }
+    }
+}
+
+/// Shortest wait before retrying after a secondary rate limit
+const SECONDARY_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
+
+/// Which of GitHub's throttles rejected a request
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum RateLimit {
+    /// The hourly request quota
+    Primary,
+    /// Abuse detection for bursts of requests, with its own wording
+    Secondary,
+}
+
+impl RateLimit {
+    fn describe(self) -> &'static str {
+        match self {
+            RateLimit::Primary => "Rate limit",
+            RateLimit::Secondary => "Secondary rate limit",
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_secs(2),
+        }
+    }
+}
+
+/// Destination for JSON-lines progress updates, shared by every fetch task
+#[derive(Clone, Debug)]
+pub struct ProgressSink(Arc<Mutex<File>>);
+
+impl ProgressSink {
+    /// Create (or truncate) the file at `path`; named pipes work too
+    pub fn create(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Ok(Self(Arc::new(Mutex::new(File::create(path)?))))
+    }
+
+    /// Record that `page` of `repo` finished, `done` of `total` pages so far
+    pub(crate) fn emit(&self, repo: &str, page: u32, total: u32, done: usize) {
+        let line = serde_json::to_string(&ProgressLine {
+            page,
+            total,
+            done,
+            repo,
+        })
+        .expect("progress lines always serialize");
+        let mut file = self
+            .0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Err(e) = writeln!(file, "{}", line).and_then(|_| file.flush()) {
+            debug!("Failed to write progress update: {}", e);
+        }
+    }
+}
+
+/// One `--progress-json` update
+#[derive(Serialize)]
+struct ProgressLine<'a> {
+    page: u32,
+    total: u32,
+    done: usize,
+    repo: &'a str,
+}
+
+/// Controls how the fork pages of a repository are fetched
+#[derive(Clone, Debug)]
+pub struct FetchOptions {
+    /// Permits bounding the requests in flight; share one pool across
+    /// repositories to bound the total regardless of how many are fetched
+    pub permits: Arc<Semaphore>,
+    pub retry: RetryPolicy,
+    /// Render an indicatif progress bar on stderr while pages are fetched
+    pub show_progress: bool,
+    /// Also write a JSON line per completed page here
+    pub progress_sink: Option<ProgressSink>,
+    /// Attach the progress bar to this group so bars of repositories fetched
+    /// at the same time don't draw over each other
+    pub progress_group: Option<MultiProgress>,
+    /// Read pages from, and write fetched pages to, this on-disk cache
+    pub cache: Option<PageCache>,
+    /// Serve pages exclusively from `cache`, failing on any missing page
+    pub offline: bool,
+    /// Skip pages that still fail after retries instead of aborting the fetch
+    pub keep_partial: bool,
+    /// Only keep forks from pages at or after this one (1-based)
+    pub since_page: Option<u32>,
+    /// Only keep forks from pages up to and including this one
+    pub until_page: Option<u32>,
+    /// Send each page's new (not yet seen) forks here as soon as it arrives,
+    /// so a report can be written while later pages are fetched. Streamed
+    /// forks are not kept in [`FetchedForks::forks`] unless a `checkpoint`
+    /// needs them.
+    pub stream: Option<UnboundedSender<Vec<Repository>>>,
+    /// Resize `permits` from the rate-limit headers of fork page responses;
+    /// `permits` must then be this controller's pool
+    pub adaptive: Option<AdaptiveConcurrency>,
+    /// Stop fetching once this many forks (before filtering) were collected
+    pub max_forks: Option<usize>,
+    /// Stop fetching at this instant, returning the forks gathered so far
+    pub deadline: Option<Instant>,
+    /// Periodically save the forks fetched so far here; the file is removed
+    /// once every page was fetched
+    pub checkpoint: Option<CheckpointFile>,
+    /// Start from the forks saved in `checkpoint`, fetching only missing pages
+    pub resume: bool,
+    /// Forks requested per page, from 1 to [`MAX_PER_PAGE`]
+    pub per_page: u32,
+    /// Warn before fetching a repository with more forks than this
+    pub warn_threshold: Option<usize>,
+    /// Also request each fork's topics; only the GraphQL query needs asking,
+    /// as REST fork listings always include them
+    pub topics: bool,
+    /// Log page counts, skipped pages, and early stops at debug level, as
+    /// for the many small fetches of a fork tree walk
+    pub quiet: bool,
+}
+
+impl Default for FetchOptions {
+    fn default() -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(10)),
+            retry: RetryPolicy::default(),
+            show_progress: false,
+            progress_group: None,
+            progress_sink: None,
+            cache: None,
+            offline: false,
+            keep_partial: false,
+            since_page: None,
+            until_page: None,
+            stream: None,
+            adaptive: None,
+            max_forks: None,
+            deadline: None,
+            checkpoint: None,
+            resume: false,
+            per_page: MAX_PER_PAGE,
+            warn_threshold: None,
+            topics: false,
+            quiet: false,
+        }
+    }
+}
+
+/// Forks collected for a repository
+#[derive(Debug, Default)]
+pub struct FetchedForks {
+    pub forks: Vec<Repository>,
+    /// Number of forks fetched, including ones only sent to `stream`
+    pub fetched: usize,
+    /// Pages skipped after failing, in ascending order (only with `keep_partial`)
+    pub failed_pages: Vec<u32>,
+    /// Fetching stopped early at `max_forks`, so `forks` is only a sample
+    pub limited: bool,
+    /// `deadline` passed before every page was fetched, so `forks` is incomplete
+    pub timed_out: bool,
+}
+
+/// Fetch every fork of `owner/repo`. The first page determines how many pages
+/// exist; the remaining pages are then fetched in parallel, or one after another
+/// by following `rel="next"` links when GitHub doesn't link the last page. Forks that appear
+/// on more than one page are returned only once. A failing page aborts the
+/// fetch unless `keep_partial` is set, in which case it is recorded and skipped.
+/// With `since_page`/`until_page`, only forks from that window of pages are
+/// returned (the first page is still fetched to learn the page count). With
+/// `max_forks`, no further pages are requested once that many were collected,
+/// and once `deadline` passes, the forks gathered so far are returned. With a
+/// `checkpoint`, the forks are saved every few pages, and `resume` starts
+/// from the saved ones.
+pub async fn fetch_all_forks(
+    octocrab: &Octocrab,
+    owner: &str,
+    repo: &str,
+    options: &FetchOptions,
+) -> Result<FetchedForks, ForkliftError> {
+    // Fetch first page to determine total pages, spinning until it arrives
+    let spinner = progress_bar(options, ProgressBar::new_spinner());
+    spinner.set_message("Determining fork count...");
+    spinner.enable_steady_tick(Duration::from_millis(100));
+    let started = Instant::now();
+    let first_page = within(
+        options.deadline,
+        fetch_first_page(octocrab, owner, repo, options),
+    )
+    .await;
+    spinner.finish_and_clear();
+    let mut collected = Collector::new(options, owner, repo);
+    if options.resume {
+        collected.restore()?;
+    }
+    let Some(first_page) = first_page else {
+        collected.timed_out = true;
+        return Ok(collected.finish());
+    };
+    let (first_items, number_of_pages, next) = first_page?;
+    debug!(
+        "Fetched the first page of forks of {}/{} in {:.2?}",
+        owner,
+        repo,
+        started.elapsed()
+    );
+    let label = format!("{}/{}", owner, repo);
+    if let Some(sink) = &options.progress_sink {
+        sink.emit(&label, 1, number_of_pages.unwrap_or(1), 1);
+    }
+
+    let first = options.since_page.unwrap_or(1);
+    let last = options.until_page.unwrap_or(u32::MAX);
+    if first <= 1 {
+        collected.add_page(1, first_items);
+    }
+    // A `last` link to page 1 is as final as no link at all
+    let more_pages = number_of_pages.map_or(next.is_some(), |total| total > 1);
+
+    // Process remaining pages in parallel if there are more
+    if let Some(total_pages) = number_of_pages.filter(|&total| total > 1 && !collected.full()) {
+        let pages = first.max(2)..=last.min(total_pages);
+        if options.since_page.is_some() || options.until_page.is_some() {
+            unless_quiet!(
+                options.quiet,
+                info,
+                "Found {} pages of forks for {}/{}; fetching pages {} to {}",
+                total_pages,
+                owner,
+                repo,
+                first.max(1),
+                last.min(total_pages)
+            );
+        } else {
+            unless_quiet!(
+                options.quiet,
+                info,
+                "Found {} pages of forks to fetch for {}/{}",
+                total_pages,
+                owner,
+                repo
+            );
+        }
+
+        let pages: Vec<u32> = pages
+            .filter(|page| !collected.pages.contains(page))
+            .collect();
+        warn_if_huge(options, owner, repo, total_pages, pages.len());
+
+        // Create progress bar
+        let progress = page_progress_bar(options, pages.len() as u64);
+        let started = Instant::now();
+        let page_count = pages.len();
+        let fetched = within(
+            options.deadline,
+            fetch_pages(
+                octocrab,
+                pages,
+                total_pages,
+                &label,
+                &progress,
+                &mut collected,
+            ),
+        )
+        .await;
+        match fetched {
+            Some(Ok(())) => {}
+            Some(Err(e)) => {
+                // Keep what was fetched so --resume can skip it next time
+                collected.save_checkpoint();
+                return Err(e);
+            }
+            // Dropping the unfinished fetch aborts its page tasks
+            None => collected.timed_out = true,
+        }
+        debug!(
+            "Fetched the other {} pages of forks of {}/{} in {:.2?}",
+            page_count,
+            owner,
+            repo,
+            started.elapsed()
+        );
+
+        if collected.full() || collected.timed_out {
+            progress.finish_and_clear();
+        } else {
+            progress.finish_with_message("All pages fetched");
+        }
+    } else if let Some(next) = next.filter(|_| number_of_pages.is_none() && !collected.full()) {
+        unless_quiet!(
+            options.quiet,
+            info,
+            "GitHub didn't say how many pages of forks {}/{} has; following its next links",
+            owner,
+            repo
+        );
+        let progress = progress_bar(options, ProgressBar::new_spinner());
+        progress.enable_steady_tick(Duration::from_millis(100));
+        let started = Instant::now();
+        let followed = within(
+            options.deadline,
+            follow_next_links(
+                octocrab,
+                next,
+                first,
+                last,
+                &label,
+                &progress,
+                &mut collected,
+            ),
+        )
+        .await;
+        progress.finish_and_clear();
+        match followed {
+            Some(Ok(())) => {}
+            Some(Err(e)) => {
+                collected.save_checkpoint();
+                return Err(e);
+            }
+            None => collected.timed_out = true,
+        }
+        debug!(
+            "Followed the next links of {}/{} in {:.2?}",
+            owner,
+            repo,
+            started.elapsed()
+        );
+    } else if !more_pages {
+        unless_quiet!(
+            options.quiet,
+            info,
+            "Only one page of forks found for {}/{}",
+            owner,
+            repo
+        );
+    }
+
+    Ok(collected.finish())
+}
+
+/// Fetch the pages after the first one by one, each from the previous page's
+/// `rel="next"` link, for when GitHub's `Link` header has no `last` link to
+/// count the pages from. A page that fails under `keep_partial` ends the walk,
+/// since the link to the page after it is lost.
+async fn follow_next_links(
+    octocrab: &Octocrab,
+    mut next: Uri,
+    first: u32,
+    last: u32,
+    label: &str,
+    progress: &ProgressBar,
+    collected: &mut Collector<'_>,
+) -> Result<(), ForkliftError> {
+    let options = collected.options;
+    let mut page = 2;
+    while page <= last && !collected.full() {
+        let permit = options.permits.acquire().await.unwrap();
+        let result = fetch_route_observed(
+            octocrab.clone(),
+            next.to_string(),
+            page,
+            options.retry,
+            options.adaptive.as_ref(),
+        )
+        .await;
+        drop(permit);
+        let mut current = match result {
+            Ok(current) => current,
+            Err(e) => {
+                collected.collect(Ok((page, Err(e.into()))))?;
+                break;
+            }
+        };
+
+        progress.set_message(format!("{} pages of forks fetched", page));
+        if let Some(sink) = &options.progress_sink {
+            // The total is unknown until the last page, so report what is known so far
+            sink.emit(label, page, page, page as usize);
+        }
+        let items = current.take_items();
+        if page >= first && !collected.pages.contains(&page) {
+            debug!("Fetched {} forks from page {}", items.len(), page);
+            collected.add_page(page, items);
+        }
+        match current.next.take() {
+            Some(link) => next = link,
+            None => break,
+        }
+        page += 1;
+    }
+    Ok(())
+}
+
+/// Warn when `owner/repo` has more forks than `options.warn_threshold`, before
+/// spending `requests` API requests on it
+fn warn_if_huge(
+    options: &FetchOptions,
+    owner: &str,
+    repo: &str,
+    total_pages: u32,
+    requests: usize,
+) {
+    let forks = total_pages as usize * options.per_page as usize;
+    if options
+        .warn_threshold
+        .is_some_and(|threshold| forks > threshold)
+    {
+        warn!(
+            "{}/{} has about {} forks; fetching them takes about {} more API requests and may take a long time. Consider --max-forks to stop after a sample, or --min-stars to keep the report short.",
+            owner, repo, forks, requests
+        );
+    }
+}
+
+/// Run `future` to completion, or until `deadline` passes, giving `None`
+pub(crate) async fn within<F: Future>(deadline: Option<Instant>, future: F) -> Option<F::Output> {
+    match deadline {
+        Some(deadline) => timeout_at(deadline, future).await.ok(),
+        None => Some(future.await),
+    }
+}
+
+/// Fetch `pages` in parallel, adding their forks to `collected`
+async fn fetch_pages(
+    octocrab: &Octocrab,
+    pages: Vec<u32>,
+    total_pages: u32,
+    label: &str,
+    progress: &ProgressBar,
+    collected: &mut Collector<'_>,
+) -> Result<(), ForkliftError> {
+    let options = collected.options;
+    let mut tasks = JoinSet::new();
+    let completed = Arc::new(AtomicUsize::new(0));
+
+    for page in pages {
+        // Take in finished pages first, so --max-forks stops spawning as soon as it is reached
+        while let Some(res) = tasks.try_join_next() {
+            collected.collect(res)?;
+        }
+        if collected.full() {
+            break;
+        }
+
+        let octo = octocrab.clone();
+        let owner_clone = collected.owner.to_string();
+        let repo_clone = collected.repo.to_string();
+        let permit = options.permits.clone().acquire_owned().await.unwrap();
+        let completed_clone = completed.clone();
+        let progress_clone = progress.clone();
+        let sink = options.progress_sink.clone();
+        let label = label.to_string();
+        let options = options.clone();
+
+        tasks.spawn(async move {
+            let _permit = permit;
+            let result = fetch_page_cached(octo, owner_clone, repo_clone, page, &options).await;
+
+            // Update progress
+            let count = completed_clone.fetch_add(1, Ordering::Relaxed) + 1;
+            progress_clone.set_position(count as u64);
+            debug!("{}: {} of {} pages fetched", label, count + 1, total_pages);
+            if let Some(sink) = &sink {
+                // The first page was already done before these tasks started
+                sink.emit(&label, page, total_pages, count + 1);
+            }
+
+            (page, result)
+        });
+    }
+
+    // Collect results as they come in
+    while let Some(res) = tasks.join_next().await {
+        collected.collect(res)?;
+        if collected.full() {
+            tasks.abort_all();
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Forks gathered from the pages of one repository as they arrive
+struct Collector<'a> {
+    options: &'a FetchOptions,
+    owner: &'a str,
+    repo: &'a str,
+    seen: HashSet<RepositoryId>,
+    forks: Vec<Repository>,
+    /// Forks collected so far, whether kept in `forks` or only streamed
+    count: usize,
+    /// Pages whose forks are all in `forks`
+    pages: BTreeSet<u32>,
+    /// Pages added since the checkpoint was last saved
+    unsaved_pages: u32,
+    duplicates: usize,
+    failed_pages: Vec<u32>,
+    /// `options.deadline` passed before every page was fetched
+    timed_out: bool,
+}
+
+impl<'a> Collector<'a> {
+    fn new(options: &'a FetchOptions, owner: &'a str, repo: &'a str) -> Self {
+        Self {
+            options,
+            owner,
+            repo,
+            seen: HashSet::new(),
+            forks: Vec::new(),
+            count: 0,
+            pages: BTreeSet::new(),
+            unsaved_pages: 0,
+            duplicates: 0,
+            failed_pages: Vec::new(),
+            timed_out: false,
+        }
+    }
+
+    /// Whether `max_forks` forks have been collected
+    fn full(&self) -> bool {
+        self.options.max_forks.is_some_and(|max| self.count >= max)
+    }
+
+    /// Start from the forks of `options.checkpoint`, if one was saved
+    fn restore(&mut self) -> Result<(), ForkliftError> {
+        let Some(file) = &self.options.checkpoint else {
+            return Ok(());
+        };
+        let Some(checkpoint) = file.load(self.owner, self.repo, self.options.per_page)? else {
+            debug!("No checkpoint at {} to resume from", file.path().display());
+            return Ok(());
+        };
+        info!(
+            "Resuming {}/{} from {}: {} forks from {} pages already fetched",
+            self.owner,
+            self.repo,
+            file.path().display(),
+            checkpoint.forks.len(),
+            checkpoint.pages.len()
+        );
+        self.pages = checkpoint.pages;
+        self.send(checkpoint.forks);
+        Ok(())
+    }
+
+    /// Keep the forks of a page not seen on an earlier one. GitHub can reorder
+    /// results between paginated requests, so the same fork may show up on two
+    /// pages.
+    fn add_page(&mut self, page_number: u32, page: Vec<Repository>) {
+        let total = page.len();
+        let new: Vec<Repository> = page
+            .into_iter()
+            .filter(|fork| self.seen.insert(fork.id))
+            .collect();
+        self.duplicates += total - new.len();
+        if self.send(new) {
+            self.pages.insert(page_number);
+            self.unsaved_pages += 1;
+        }
+        if let Some(file) = &self.options.checkpoint {
+            if self.unsaved_pages >= file.every() {
+                self.save_checkpoint();
+            }
+        }
+    }
+
+    /// Keep `forks`, up to `max_forks`, also sending them to `options.stream`.
+    /// Returns whether all of them were kept.
+    fn send(&mut self, mut forks: Vec<Repository>) -> bool {
+        self.seen.extend(forks.iter().map(|fork| fork.id));
+        let total = forks.len();
+        if let Some(max) = self.options.max_forks {
+            forks.truncate(max.saturating_sub(self.count));
+        }
+        let complete = forks.len() == total;
+        self.count += forks.len();
+        match &self.options.stream {
+            // Checkpoints save every fork, so they have to be kept anyway
+            Some(stream) if self.options.checkpoint.is_some() => {
+                let _ = stream.send(forks.clone());
+                self.forks.extend(forks);
+            }
+            // A closed receiver means the writer already failed; its error is reported there
+            Some(stream) => {
+                let _ = stream.send(forks);
+            }
+            None => self.forks.extend(forks),
+        }
+        complete
+    }
+
+    /// Save the forks collected so far to `options.checkpoint`. A failed save
+    /// only costs the ability to resume, so it doesn't stop the fetch.
+    fn save_checkpoint(&mut self) {
+        let Some(file) = &self.options.checkpoint else {
+            return;
+        };
+        if let Err(e) = file.save(
+            self.owner,
+            self.repo,
+            self.options.per_page,
+            &self.pages,
+            &self.forks,
+        ) {
+            warn!("Could not save checkpoint {}: {}", file.path().display(), e);
+        }
+        self.unsaved_pages = 0;
+    }
+
+    /// Take in the outcome of one page task
+    fn collect(
+        &mut self,
+        res: Result<(u32, Result<Vec<Repository>, ForkliftError>), JoinError>,
+    ) -> Result<(), ForkliftError> {
+        match res {
+            Ok((page, Ok(items))) => {
+                debug!("Fetched {} forks from page {}", items.len(), page);
+                self.add_page(page, items);
+                Ok(())
+            }
+            Ok((page, Err(e))) if self.options.keep_partial => {
+                unless_quiet!(
+                    self.options.quiet,
+                    warn,
+                    "Skipping page {} of {}/{}: {}",
+                    page,
+                    self.owner,
+                    self.repo,
+                    e
+                );
+                self.failed_pages.push(page);
+                Ok(())
+            }
+            Ok((page, Err(e))) => {
+                error!("Failed to fetch page {}: {}", page, e);
+                Err(e)
+            }
+            Err(e) => {
+                error!("Task join error: {}", e);
+                Err(e.into())
+            }
+        }
+    }
+
+    fn finish(mut self) -> FetchedForks {
+        if self.duplicates > 0 {
+            debug!("Dropped {} duplicate forks across pages", self.duplicates);
+        }
+        let limited = self.full();
+        if limited {
+            unless_quiet!(
+                self.options.quiet,
+                info,
+                "Stopped after {} forks of {}/{} (--max-forks)",
+                self.count,
+                self.owner,
+                self.repo
+            );
+        }
+        if self.timed_out {
+            unless_quiet!(
+                self.options.quiet,
+                warn,
+                "Ran out of time after fetching {} forks of {}/{}",
+                self.count,
+                self.owner,
+                self.repo
+            );
+        }
+        if let Some(file) = &self.options.checkpoint {
+            // Pages still missing can be fetched later with --resume
+            if self.timed_out || !self.failed_pages.is_empty() {
+                self.save_checkpoint();
+            } else {
+                file.remove();
+            }
+        }
+        self.failed_pages.sort_unstable();
+        FetchedForks {
+            forks: self.forks,
+            fetched: self.count,
+            failed_pages: self.failed_pages,
+            limited,
+            timed_out: self.timed_out,
+        }
+    }
+}
+
+/// How many fork pages of `per_page` forks an already looked up `upstream`
+/// has, going by its fork count
+pub fn fork_page_count(upstream: &Repository, per_page: u32) -> u32 {
+    let forks = upstream.forks_count.unwrap_or(0);
+    forks.div_ceil(per_page.max(1)).max(1)
+}
+
+/// Show `bar` on stderr (inside the progress group, if any), or hide it
+/// when progress is disabled
+pub(crate) fn progress_bar(options: &FetchOptions, bar: ProgressBar) -> ProgressBar {
+    match (&options.progress_group, options.show_progress) {
+        (Some(group), true) => group.add(bar),
+        (None, true) => bar,
+        (_, false) => ProgressBar::hidden(),
+    }
+}
+
+/// Progress bar counting fetched pages out of `pages`
+pub(crate) fn page_progress_bar(options: &FetchOptions, pages: u64) -> ProgressBar {
+    let bar = progress_bar(options, ProgressBar::new(pages));
+    bar.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} pages ({eta})")
+            .expect("Invalid progress bar template")
+            .progress_chars("#>-")
+    );
+    bar
+}
+
+/// Fetch the first fork page, returning its items and the total page count.
+/// Served from the cache when a fresh entry exists (or always, when offline).
+async fn fetch_first_page(
+    octocrab: &Octocrab,
+    owner: &str,
+    repo: &str,
+    options: &FetchOptions,
+) -> Result<(Vec<Repository>, Option<u32>, Option<Uri>), ForkliftError> {
+    if let Some(cache) = &options.cache {
+        if let Some(cached) = cache.load(owner, repo, 1, options.offline).await {
+            return Ok((cached.items, cached.total_pages, None));
+        }
+    }
+    if options.offline {
+        return Err(ForkliftError::CacheMiss {
+            owner: owner.to_string(),
+            repo: repo.to_string(),
+            page: 1,
+        });
+    }
+
+    debug!("Fetching initial page to determine fork count");
+    let _permit = options.permits.acquire().await.unwrap();
+    let mut current_page = fetch_page_observed(
+        octocrab.clone(),
+        owner.to_string(),
+        repo.to_string(),
+        1,
+        options.per_page,
+        options.retry,
+        options.adaptive.as_ref(),
+    )
+    .await
+    .map_err(|e| match e {
+        octocrab::Error::GitHub { source, .. } if source.status_code == StatusCode::NOT_FOUND => {
+            ForkliftError::RepoNotFound {
+                owner: owner.to_string(),
+                repo: repo.to_string(),
+            }
+        }
+        e => e.into(),
+    })?;
+
+    let number_of_pages = current_page.number_of_pages();
+    let items = current_page.take_items();
+    if let Some(cache) = &options.cache {
+        cache.store(owner, repo, 1, number_of_pages, &items).await?;
+    }
+
+    Ok((items, number_of_pages, current_page.next))
+}
+
+/// Fetch a single page through the cache: cached entries are returned as-is,
+/// misses are fetched from the API (unless offline) and then stored.
+async fn fetch_page_cached(
+    octocrab: Octocrab,
+    owner: String,
+    repo: String,
+    page: u32,
+    options: &FetchOptions,
+) -> Result<Vec<Repository>, ForkliftError> {
+    if let Some(cache) = &options.cache {
+        if let Some(cached) = cache.load(&owner, &repo, page, options.offline).await {
+            return Ok(cached.items);
+        }
+    }
+    if options.offline {
+        return Err(ForkliftError::CacheMiss { owner, repo, page });
+    }
+
+    let items = fetch_page_observed(
+        octocrab,
+        owner.clone(),
+        repo.clone(),
+        page,
+        options.per_page,
+        options.retry,
+        options.adaptive.as_ref(),
+    )
+    .await?
+    .take_items();
+    if let Some(cache) = &options.cache {
+        cache.store(&owner, &repo, page, None, &items).await?;
+    }
+    Ok(items)
+}
+
+/// Fetch a single fork page and retry if GitHub's rate limit is hit, GitHub
+/// answers with a 5xx status, or the request times out or loses its
+/// connection. Waits for the duration advised by GitHub's `Retry-After` or
+/// `x-ratelimit-reset` headers when present, falling back to exponential backoff.
+/// The whole page, of up to `per_page` forks, is returned so the first one can
+/// report how many pages exist.
+pub async fn fetch_page_with_retry(
+    octocrab: Octocrab,
+    owner: String,
+    repo: String,
+    page: u32,
+    per_page: u32,
+    retry: RetryPolicy,
+) -> Result<Page<Repository>, octocrab::Error> {
+    fetch_page_observed(octocrab, owner, repo, page, per_page, retry, None).await
+}
+
+/// [`fetch_page_with_retry`], feeding every response's rate-limit headers to `adaptive`
+async fn fetch_page_observed(
+    octocrab: Octocrab,
+    owner: String,
+    repo: String,
+    page: u32,
+    per_page: u32,
+    retry: RetryPolicy,
+    adaptive: Option<&AdaptiveConcurrency>,
+) -> Result<Page<Repository>, octocrab::Error> {
+    let route = format!(
+        "/repos/{}/{}/forks?per_page={}&page={}",
+        owner, repo, per_page, page
+    );
+    fetch_route_observed(octocrab, route, page, retry, adaptive).await
+}
+
+/// Fetch fork page number `page` from `route`, a path or a `Link` header URL,
+/// retrying as [`fetch_page_with_retry`] does
+async fn fetch_route_observed(
+    octocrab: Octocrab,
+    route: String,
+    page: u32,
+    retry: RetryPolicy,
+    adaptive: Option<&AdaptiveConcurrency>,
+) -> Result<Page<Repository>, octocrab::Error> {
+    let mut attempts = 0;
+
+    loop {
+        let response = match octocrab._get(route.as_str()).await {
+            Ok(response) => response,
+            Err(err) if is_transient(&err) && attempts < retry.max_retries => {
+                attempts += 1;
+                let wait = retry.backoff(attempts);
+                warn!(
+                    "Request for page {} failed ({}), retrying in {:.1}s (attempt {}/{})",
+                    page,
+                    err,
+                    wait.as_secs_f64(),
+                    attempts,
+                    retry.max_retries
+                );
+                sleep(wait).await;
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
+        if let Some(adaptive) = adaptive {
+            adaptive.observe(response.headers());
+        }
+
+        if response.status().is_success() {
+            if attempts > 0 {
+                debug!(
+                    "Successfully fetched page {} after {} retries",
+                    page, attempts
+                );
+            }
+            return Page::<Repository>::from_response(response).await;
+        }
+
+        // Headers are lost once the response is mapped into an octocrab error
+        let advised_wait = server_advised_wait(response.headers());
+        let status = response.status();
+        if status.is_server_error() && attempts < retry.max_retries {
+            attempts += 1;
+            let wait = advised_wait.unwrap_or_else(|| retry.backoff(attempts));
+            warn!(
+                "GitHub returned {} for page {}, retrying in {:.1}s (attempt {}/{})",
+                status,
+                page,
+                wait.as_secs_f64(),
+                attempts,
+                retry.max_retries
+            );
+            sleep(wait).await;
+            continue;
+        }
+        let err = match octocrab::map_github_error(response).await {
+            Err(err) => err,
+            Ok(_) => unreachable!("non-success responses always map to an error"),
+        };
+
+        if let Some(limit) = rate_limit_kind(&err).filter(|_| attempts < retry.max_retries) {
+            attempts += 1;
+            // Prefer the server's advice; otherwise back off exponentially
+            let wait = advised_wait.unwrap_or_else(|| retry.rate_limit_backoff(limit, attempts));
+            warn!(
+                "{} hit on page {}, retrying in {:.1}s (attempt {}/{})",
+                limit.describe(),
+                page,
+                wait.as_secs_f64(),
+                attempts,
+                retry.max_retries
+            );
+            sleep(wait).await;
+            continue;
+        }
+
+        return Err(err);
+    }
+}
+
+/// Which rate limit, if any, made GitHub reject the request. Secondary
+/// limits are reported both as "You have exceeded a secondary rate limit"
+/// and in older "abuse detection" wording, with a 403 or 429 status.
+pub(crate) fn rate_limit_kind(err: &octocrab::Error) -> Option<RateLimit> {
+    let octocrab::Error::GitHub { source, .. } = err else {
+        return None;
+    };
+    if !matches!(
+        source.status_code,
+        StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS
+    ) {
+        return None;
+    }
+    let message = source.message.to_ascii_lowercase();
+    if message.contains("secondary rate limit") || message.contains("abuse") {
+        Some(RateLimit::Secondary)
+    } else if message.contains("rate limit") {
+        Some(RateLimit::Primary)
+    } else {
+        None
+    }
+}
+
+/// Whether a request failed without a response for a reason worth retrying:
+/// a connect/read/write timeout, or a connection that was refused, reset, or
+/// closed before the response was complete. TLS, proxy, DNS, and URI errors
+/// fail the same way on every attempt, so they are returned at once.
+pub(crate) fn is_transient(err: &octocrab::Error) -> bool {
+    use std::io::ErrorKind;
+    let mut source: Option<&(dyn std::error::Error + 'static)> = Some(err);
+    while let Some(e) = source {
+        // GitHub (or a proxy) closed the connection before the response was complete
+        if let Some(hyper) = e.downcast_ref::<hyper::Error>() {
+            if hyper.is_incomplete_message() || hyper.is_closed() {
+                return true;
+            }
+        }
+        if let Some(io) = e.downcast_ref::<std::io::Error>() {
+            if matches!(
+                io.kind(),
+                ErrorKind::TimedOut
+                    | ErrorKind::ConnectionRefused
+                    | ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+                    | ErrorKind::BrokenPipe
+                    | ErrorKind::UnexpectedEof
+            ) {
+                return true;
+            }
+        }
+        source = e.source();
+    }
+    false
+}
+
+/// Whether GitHub answered with a 5xx status (502 and 503 are common during
+/// incidents)
+pub(crate) fn is_server_error(err: &octocrab::Error) -> bool {
+    matches!(err, octocrab::Error::GitHub { source, .. } if source.status_code.is_server_error())
+}
+
+/// Determine how long GitHub asked us to wait before retrying, if it said so.
+/// `Retry-After` (in seconds) takes priority; otherwise, when the primary quota
+/// is exhausted, wait until the `x-ratelimit-reset` epoch timestamp.
+pub fn server_advised_wait(headers: &HeaderMap) -> Option<Duration> {
+    let header_u64 = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<u64>().ok())
+    };
+
+    if let Some(secs) = header_u64("retry-after") {
+        return Some(Duration::from_secs(secs));
+    }
+
+    if header_u64("x-ratelimit-remaining") == Some(0) {
+        if let Some(reset) = header_u64("x-ratelimit-reset") {
+            let now = Utc::now().timestamp().max(0) as u64;
+            // Add a second of slack so we don't wake up just before the reset
+            return Some(Duration::from_secs(reset.saturating_sub(now) + 1));
+        }
+    }
+
+    None
+}
@@ -0,0 +1,326 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use clap::ValueEnum;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use octocrab::models::Repository;
+use rand::rngs::Xoshiro256PlusPlus;
+use rand::SeedableRng;
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::path::Path;
+use tracing::{debug, warn};
+
+use crate::{ForkRecord, ForkliftError};
+
+/// Keys the report can be sorted by
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortKey {
+    /// Most-starred forks first
+    Stars,
+    /// Alphabetical by organization, then fork name
+    Name,
+    /// Most recently updated forks first
+    Updated,
+}
+
+/// Which fetched forks make it into the report
+#[derive(Clone, Debug, Default)]
+pub struct FilterOptions {
+    /// Drop forks with fewer stars than this
+    pub min_stars: u32,
+    /// Keep user-owned forks as well as organization-owned ones
+    pub include_users: bool,
+    /// Drop forks not pushed to since this time
+    pub active_since: Option<DateTime<Utc>>,
+    /// Keep private and internal forks the token can see, not just public ones
+    pub include_private: bool,
+    /// Drop archived forks
+    pub exclude_archived: bool,
+    /// When non-empty, keep only forks whose owner login is listed (case-insensitive)
+    pub org_allow: Vec<String>,
+    /// Drop forks whose owner login is listed (case-insensitive); wins over `org_allow`
+    pub org_deny: Vec<String>,
+    /// When set, keep only forks whose repository name matches
+    pub name_pattern: Option<Regex>,
+    /// Drop forks whose `owner/name` matches one of these globs (case-insensitive)
+    pub ignore: Option<GlobSet>,
+}
+
+/// Ignore file picked up from the working directory when `--ignore-file` isn't given
+pub const DEFAULT_IGNORE_FILE: &str = ".forkliftignore";
+
+/// Load the glob patterns of the ignore file at `path`, or of
+/// [`DEFAULT_IGNORE_FILE`] if it exists when no path is given. One pattern
+/// per line is matched against `owner/name`; a pattern without a `/` matches
+/// every fork of that owner. Blank lines and lines starting with `#` are skipped.
+pub fn load_ignore_file(path: Option<&str>) -> Result<Option<GlobSet>, ForkliftError> {
+    let path = match path {
+        Some(path) => path,
+        None if Path::new(DEFAULT_IGNORE_FILE).is_file() => DEFAULT_IGNORE_FILE,
+        None => return Ok(None),
+    };
+    let invalid = |message: String| ForkliftError::InvalidIgnoreFile {
+        path: path.to_string(),
+        message,
+    };
+    let contents = std::fs::read_to_string(path).map_err(|e| invalid(e.to_string()))?;
+
+    let mut builder = GlobSetBuilder::new();
+    let mut patterns = 0;
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let pattern = if line.contains('/') {
+            line.to_string()
+        } else {
+            format!("{}/*", line)
+        };
+        let glob = GlobBuilder::new(&pattern)
+            .case_insensitive(true)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| invalid(format!("line {}: {}", number + 1, e)))?;
+        builder.add(glob);
+        patterns += 1;
+    }
+    debug!(
+        "Ignoring forks matching {} patterns from {}",
+        patterns, path
+    );
+    builder
+        .build()
+        .map(Some)
+        .map_err(|e| invalid(e.to_string()))
+}
+
+/// Load the organization logins an `--expected` file allows to fork, one per
+/// line, lowercased for case-insensitive matching. Blank lines and lines
+/// starting with `#` are skipped.
+pub fn load_expected_owners(path: &str) -> Result<HashSet<String>, ForkliftError> {
+    let contents =
+        std::fs::read_to_string(path).map_err(|e| ForkliftError::InvalidExpectedFile {
+            path: path.to_string(),
+            message: e.to_string(),
+        })?;
+    let owners: HashSet<String> = contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_lowercase)
+        .collect();
+    debug!(
+        "Expecting forks from {} organizations listed in {}",
+        owners.len(),
+        path
+    );
+    Ok(owners)
+}
+
+/// Mark every organization fork whose owner isn't in `expected` (as loaded
+/// by [`load_expected_owners`]), returning how many were marked
+pub fn mark_unexpected(forks: &mut [ForkRecord], expected: &HashSet<String>) -> usize {
+    let mut unexpected = 0;
+    for fork in forks.iter_mut() {
+        if fork.owner_type != "Organization" {
+            continue;
+        }
+        let is_unexpected = !expected.contains(&fork.organization.to_lowercase());
+        fork.unexpected = Some(is_unexpected);
+        unexpected += usize::from(is_unexpected);
+    }
+    unexpected
+}
+
+/// Compile a `--filter-name` pattern
+pub fn parse_name_pattern(raw: &str) -> Result<Regex, ForkliftError> {
+    Regex::new(raw).map_err(|e| ForkliftError::InvalidNamePattern {
+        pattern: raw.to_string(),
+        message: e.to_string(),
+    })
+}
+
+/// Parse an `--active-since` value: a `YYYY-MM-DD` date, an RFC 3339
+/// timestamp, or a relative age in days such as `90d`
+pub fn parse_since(raw: &str) -> Result<DateTime<Utc>, ForkliftError> {
+    let invalid = || ForkliftError::InvalidDate(raw.to_string());
+    let raw = raw.trim();
+
+    if let Some(days) = raw.strip_suffix('d') {
+        let days: i64 = days.parse().map_err(|_| invalid())?;
+        let age = Duration::try_days(days).ok_or_else(invalid)?;
+        return Utc::now().checked_sub_signed(age).ok_or_else(invalid);
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(raw, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).ok_or_else(invalid)?.and_utc());
+    }
+    DateTime::parse_from_rfc3339(raw)
+        .map(|time| time.with_timezone(&Utc))
+        .map_err(|_| invalid())
+}
+
+/// Forks kept by [`filter_forks`] or [`filter_records`]
+#[derive(Debug, Default)]
+pub struct FilteredForks {
+    pub records: Vec<ForkRecord>,
+    /// Private and internal forks left out for lack of `include_private`,
+    /// for the caller to report once per repository
+    pub private_left_out: usize,
+}
+
+/// Filter organization (or, with `include_users`, all) forks meeting the
+/// star threshold, activity cutoff, visibility, archive status, and owner
+/// allow/deny lists and convert them into report records.
+pub fn filter_forks(forks: Vec<Repository>, options: &FilterOptions) -> FilteredForks {
+    let records = forks.into_iter().filter_map(to_record).collect();
+    filter_records(records, options)
+}
+
+/// Apply the same filters as [`filter_forks`] to forks that are already
+/// report records (e.g. fetched through GraphQL).
+pub fn filter_records(records: Vec<ForkRecord>, options: &FilterOptions) -> FilteredForks {
+    let private_left_out = records
+        .iter()
+        .filter(|fork| !options.include_private && fork.visibility != "public")
+        .filter(|fork| options.include_users || fork.owner_type == "Organization")
+        .count();
+    let records = records
+        .into_iter()
+        .filter(|fork| fork.stars >= options.min_stars)
+        .filter(|fork| {
+            options
+                .active_since
+                .is_none_or(|since| fork.pushed_at.is_some_and(|pushed| pushed >= since))
+        })
+        .filter(|fork| options.include_users || fork.owner_type == "Organization")
+        .filter(|fork| options.include_private || fork.visibility == "public")
+        .filter(|fork| !(options.exclude_archived && fork.archived))
+        .filter(|fork| owner_allowed(&fork.organization, options))
+        .filter(|fork| {
+            options
+                .name_pattern
+                .as_ref()
+                .is_none_or(|pattern| pattern.is_match(&fork.fork_name))
+        })
+        .filter(|fork| {
+            options.ignore.as_ref().is_none_or(|ignore| {
+                !ignore.is_match(format!("{}/{}", fork.organization, fork.fork_name))
+            })
+        })
+        .collect();
+    FilteredForks {
+        records,
+        private_left_out,
+    }
+}
+
+/// Apply `--org-deny`, then `--org-allow`, to a fork owner's login
+fn owner_allowed(login: &str, options: &FilterOptions) -> bool {
+    let listed = |logins: &[String]| logins.iter().any(|l| l.trim().eq_ignore_ascii_case(login));
+    if listed(&options.org_deny) {
+        return false;
+    }
+    options.org_allow.is_empty() || listed(&options.org_allow)
+}
+
+/// Convert a REST fork into a report record. Forks GitHub returned without
+/// an owner or name are dropped with a warning, so incomplete data isn't
+/// mistaken for a fork that was filtered out.
+pub(crate) fn to_record(fork: Repository) -> Option<ForkRecord> {
+    let Some(owner) = fork.owner.filter(|owner| !owner.login.is_empty()) else {
+        warn!(
+            "Dropping fork {} ({}): GitHub returned it without an owner",
+            fork.id,
+            fork.full_name.as_deref().unwrap_or(&fork.name)
+        );
+        return None;
+    };
+    if fork.name.is_empty() {
+        warn!(
+            "Dropping fork {} of {}: GitHub returned it without a name",
+            fork.id, owner.login
+        );
+        return None;
+    }
+    Some(ForkRecord {
+        id: Some(fork.id.0),
+        organization: owner.login,
+        fork_name: fork.name,
+        url: fork.html_url.map(|u| u.to_string()).unwrap_or_default(),
+        stars: fork.stargazers_count.unwrap_or(0),
+        watchers: fork
+            .subscribers_count
+            .and_then(|count| u32::try_from(count).ok())
+            .unwrap_or(0),
+        open_issues: fork.open_issues_count.unwrap_or(0),
+        forks: fork.forks_count.unwrap_or(0),
+        language: fork
+            .language
+            .and_then(|language| language.as_str().map(str::to_string)),
+        owner_type: owner.r#type,
+        updated_at: fork.updated_at,
+        pushed_at: fork.pushed_at,
+        archived: fork.archived.unwrap_or(false),
+        visibility: fork.visibility.unwrap_or_else(|| {
+            let private = fork.private.unwrap_or(false);
+            if private { "private" } else { "public" }.to_string()
+        }),
+        default_branch: fork.default_branch,
+        ahead: None,
+        behind: None,
+        open_prs: None,
+        depth: None,
+        parent: None,
+        license: fork.license.map(|license| license.spdx_id),
+        license_changed: None,
+        topics: fork.topics,
+        unexpected: None,
+    })
+}
+
+/// Sort forks in place by the given key. Ties fall back to alphabetical
+/// name ordering so output is deterministic across runs.
+pub fn sort_forks(forks: &mut [ForkRecord], key: SortKey) {
+    let by_name = |a: &ForkRecord, b: &ForkRecord| {
+        a.organization
+            .cmp(&b.organization)
+            .then_with(|| a.fork_name.cmp(&b.fork_name))
+    };
+
+    match key {
+        SortKey::Stars => forks.sort_by(|a, b| b.stars.cmp(&a.stars).then_with(|| by_name(a, b))),
+        SortKey::Name => forks.sort_by(by_name),
+        SortKey::Updated => {
+            forks.sort_by(|a, b| b.updated_at.cmp(&a.updated_at).then_with(|| by_name(a, b)))
+        }
+    }
+}
+
+/// Keep a random sample of `size` forks, chosen by a generator seeded with
+/// `seed`. The forks are ranked by name before sampling, so the same seed picks
+/// the same forks however the pages arrived; the kept forks stay in their
+/// original order.
+pub fn sample_forks(forks: &mut Vec<ForkRecord>, size: usize, seed: u64) {
+    if forks.len() <= size {
+        return;
+    }
+    let mut by_name: Vec<usize> = (0..forks.len()).collect();
+    by_name.sort_by(|&a, &b| {
+        let (a, b) = (&forks[a], &forks[b]);
+        a.organization
+            .cmp(&b.organization)
+            .then_with(|| a.fork_name.cmp(&b.fork_name))
+            .then_with(|| a.url.cmp(&b.url))
+    });
+
+    let mut rng = Xoshiro256PlusPlus::seed_from_u64(seed);
+    let mut keep = vec![false; forks.len()];
+    for picked in rand::seq::index::sample(&mut rng, forks.len(), size) {
+        keep[by_name[picked]] = true;
+    }
+    let mut keep = keep.into_iter();
+    forks.retain(|_| keep.next().unwrap_or(false));
+    debug!("Sampled {} forks with seed {}", forks.len(), seed);
+}
@@ -0,0 +1,368 @@
+use chrono::{DateTime, Utc};
+use indicatif::ProgressBar;
+use octocrab::Octocrab;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::time::{sleep, Duration, Instant};
+use tracing::{debug, info, warn};
+
+use crate::fetch::{
+    is_server_error, is_transient, page_progress_bar, progress_bar, rate_limit_kind, within,
+    RateLimit,
+};
+use crate::{FetchOptions, ForkRecord, ForkliftError, RetryPolicy, ENTERPRISE_API_PATH};
+
+/// Only the fork fields the report uses, paged by cursor
+const FORKS_QUERY: &str = r#"
+query($owner: String!, $name: String!, $first: Int!, $cursor: String, $topics: Boolean!) {
+  repository(owner: $owner, name: $name) {
+    forks(first: $first, after: $cursor) {
+      totalCount
+      pageInfo { hasNextPage endCursor }
+      nodes {
+        databaseId
+        name
+        url
+        stargazerCount
+        watchers { totalCount }
+        issues(states: OPEN) { totalCount }
+        forkCount
+        licenseInfo { spdxId }
+        repositoryTopics(first: 20) @include(if: $topics) { nodes { topic { name } } }
+        primaryLanguage { name }
+        pushedAt
+        updatedAt
+        isArchived
+        visibility
+        defaultBranchRef { name }
+        owner { __typename login }
+      }
+    }
+  }
+}
+"#;
+
+#[derive(Debug, Deserialize)]
+struct QueryResponse {
+    data: Option<QueryData>,
+    #[serde(default)]
+    errors: Vec<QueryError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct QueryError {
+    message: String,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct QueryData {
+    repository: Option<RepositoryForks>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RepositoryForks {
+    forks: ForkConnection,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ForkConnection {
+    total_count: u64,
+    page_info: PageInfo,
+    nodes: Vec<ForkNode>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PageInfo {
+    has_next_page: bool,
+    end_cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ForkNode {
+    database_id: Option<u64>,
+    name: String,
+    url: String,
+    stargazer_count: u32,
+    watchers: Count,
+    issues: Count,
+    fork_count: u32,
+    /// Missing when GitHub detected no license
+    license_info: Option<LicenseInfo>,
+    /// Only requested with `--include-topics`
+    repository_topics: Option<TopicConnection>,
+    primary_language: Option<Language>,
+    pushed_at: Option<DateTime<Utc>>,
+    updated_at: Option<DateTime<Utc>>,
+    is_archived: bool,
+    /// `PUBLIC`, `PRIVATE`, or `INTERNAL`
+    visibility: String,
+    /// Missing for empty repositories
+    default_branch_ref: Option<BranchRef>,
+    owner: ForkOwner,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Count {
+    total_count: u32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LicenseInfo {
+    spdx_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TopicConnection {
+    nodes: Vec<TopicNode>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TopicNode {
+    topic: Topic,
+}
+
+#[derive(Debug, Deserialize)]
+struct Topic {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Language {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct BranchRef {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ForkOwner {
+    /// `Organization` or `User`, matching the REST owner type
+    #[serde(rename = "__typename")]
+    typename: String,
+    login: String,
+}
+
+impl From<ForkNode> for ForkRecord {
+    fn from(node: ForkNode) -> Self {
+        ForkRecord {
+            id: node.database_id,
+            organization: node.owner.login,
+            fork_name: node.name,
+            url: node.url,
+            stars: node.stargazer_count,
+            watchers: node.watchers.total_count,
+            open_issues: node.issues.total_count,
+            forks: node.fork_count,
+            language: node.primary_language.map(|language| language.name),
+            owner_type: node.owner.typename,
+            updated_at: node.updated_at,
+            pushed_at: node.pushed_at,
+            archived: node.is_archived,
+            visibility: node.visibility.to_lowercase(),
+            default_branch: node.default_branch_ref.map(|branch| branch.name),
+            ahead: None,
+            behind: None,
+            open_prs: None,
+            depth: None,
+            parent: None,
+            license: node.license_info.and_then(|license| license.spdx_id),
+            license_changed: None,
+            topics: node.repository_topics.map(|topics| {
+                topics
+                    .nodes
+                    .into_iter()
+                    .map(|node| node.topic.name)
+                    .collect()
+            }),
+            unexpected: None,
+        }
+    }
+}
+
+/// API base for a client that sends GraphQL queries to a GitHub Enterprise
+/// host. Octocrab posts queries to `/graphql` under the base URI, but
+/// Enterprise serves GraphQL at `/api/graphql` rather than under `/api/v3`.
+pub fn enterprise_graphql_base(api_base: &str) -> String {
+    let host = api_base
+        .strip_suffix(ENTERPRISE_API_PATH)
+        .unwrap_or(api_base);
+    format!("{}/api", host)
+}
+
+/// Fetch every fork of `owner/repo` through the GraphQL API, requesting only
+/// the fields the report needs. Pages are cursor-linked, so
+/// they are fetched one after another; the cache and `keep_partial` don't apply.
+/// Once `deadline` passes, the forks gathered so far are returned.
+pub async fn fetch_fork_records_graphql(
+    octocrab: &Octocrab,
+    owner: &str,
+    repo: &str,
+    options: &FetchOptions,
+) -> Result<Vec<ForkRecord>, ForkliftError> {
+    let spinner = progress_bar(options, ProgressBar::new_spinner());
+    spinner.set_message("Determining fork count...");
+    spinner.enable_steady_tick(Duration::from_millis(100));
+
+    let mut records = Vec::new();
+    let mut cursor: Option<String> = None;
+    let mut progress: Option<ProgressBar> = None;
+    let mut pages_done: u32 = 0;
+    let label = format!("{}/{}", owner, repo);
+
+    loop {
+        let connection = within(options.deadline, async {
+            let _permit = options.permits.acquire().await.unwrap();
+            query_forks(
+                octocrab,
+                owner,
+                repo,
+                cursor.as_deref(),
+                options.per_page,
+                options.topics,
+                options.retry,
+            )
+            .await
+        })
+        .await;
+        let connection = match connection {
+            Some(Ok(connection)) => connection,
+            Some(Err(e)) => {
+                spinner.finish_and_clear();
+                return Err(e);
+            }
+            None => {
+                spinner.finish_and_clear();
+                warn!(
+                    "Ran out of time after fetching {} forks of {}/{}",
+                    records.len(),
+                    owner,
+                    repo
+                );
+                break;
+            }
+        };
+
+        let bar = progress.get_or_insert_with(|| {
+            spinner.finish_and_clear();
+            let total_pages = connection.total_count.div_ceil(u64::from(options.per_page));
+            info!(
+                "Found {} forks ({} GraphQL pages) to fetch for {}/{}",
+                connection.total_count, total_pages, owner, repo
+            );
+            page_progress_bar(options, total_pages)
+        });
+        bar.inc(1);
+        pages_done += 1;
+        if let Some(sink) = &options.progress_sink {
+            let total_pages = connection
+                .total_count
+                .div_ceil(u64::from(options.per_page))
+                .max(1) as u32;
+            sink.emit(&label, pages_done, total_pages, pages_done as usize);
+        }
+
+        debug!("Fetched {} forks from GraphQL page", connection.nodes.len());
+        records.extend(connection.nodes.into_iter().map(ForkRecord::from));
+
+        match connection.page_info.end_cursor {
+            Some(next) if connection.page_info.has_next_page => cursor = Some(next),
+            _ => break,
+        }
+    }
+
+    if let Some(bar) = progress {
+        if options
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            bar.finish_and_clear();
+        } else {
+            bar.finish_with_message("All pages fetched");
+        }
+    }
+    Ok(records)
+}
+
+/// Run one page of the forks query, retrying timeouts and rate limits with
+/// exponential backoff
+async fn query_forks(
+    octocrab: &Octocrab,
+    owner: &str,
+    repo: &str,
+    cursor: Option<&str>,
+    per_page: u32,
+    topics: bool,
+    retry: RetryPolicy,
+) -> Result<ForkConnection, ForkliftError> {
+    let variables = json!({
+        "owner": owner,
+        "name": repo,
+        "first": per_page,
+        "cursor": cursor,
+        "topics": topics,
+    });
+    let body = json!({ "query": FORKS_QUERY, "variables": variables });
+    let mut attempts = 0;
+
+    loop {
+        let outcome: Result<QueryResponse, octocrab::Error> = octocrab.graphql(&body).await;
+
+        // GraphQL reports its primary rate limit as a query error on a 200
+        // response, while secondary limits still arrive as HTTP errors
+        let secondary =
+            matches!(&outcome, Err(err) if rate_limit_kind(err) == Some(RateLimit::Secondary));
+        let retryable = match &outcome {
+            Ok(response) => response
+                .errors
+                .iter()
+                .any(|e| e.kind.as_deref() == Some("RATE_LIMITED")),
+            Err(err) => is_transient(err) || is_server_error(err) || rate_limit_kind(err).is_some(),
+        };
+        if retryable && attempts < retry.max_retries {
+            attempts += 1;
+            let wait = if secondary {
+                retry.secondary_backoff(attempts)
+            } else {
+                retry.backoff(attempts)
+            };
+            warn!(
+                "GraphQL request was rate limited or failed transiently, retrying in {:.1}s (attempt {}/{})",
+                wait.as_secs_f64(),
+                attempts,
+                retry.max_retries
+            );
+            sleep(wait).await;
+            continue;
+        }
+
+        let response = outcome?;
+        if let Some(repository) = response.data.and_then(|data| data.repository) {
+            return Ok(repository.forks);
+        }
+        if response
+            .errors
+            .iter()
+            .any(|e| e.kind.as_deref() == Some("NOT_FOUND"))
+        {
+            return Err(ForkliftError::RepoNotFound {
+                owner: owner.to_string(),
+                repo: repo.to_string(),
+            });
+        }
+        let messages: Vec<String> = response.errors.into_iter().map(|e| e.message).collect();
+        return Err(ForkliftError::GraphqlError(if messages.is_empty() {
+            "no repository data returned".to_string()
+        } else {
+            messages.join("; ")
+        }));
+    }
+}
@@ -0,0 +1,62 @@
+//! Core library behind the `forklift` CLI: parse GitHub repository URLs,
+//! fetch every fork of a repository in parallel, filter and sort them, and
+//! write the resulting report in one of several formats.
+
+mod adaptive;
+mod cache;
+mod checkpoint;
+mod client;
+mod config;
+mod diff;
+mod divergence;
+mod error;
+mod exec;
+mod fetch;
+mod filter;
+mod graphql;
+mod parent;
+mod post;
+mod pulls;
+mod repo;
+mod report;
+mod s3;
+mod stream;
+mod summary;
+mod tree;
+
+pub use adaptive::{auto_concurrency, AdaptiveConcurrency, AUTO_CONCURRENCY_MAX};
+pub use cache::{CachedPage, PageCache, DEFAULT_CACHE_DIR};
+pub use checkpoint::{Checkpoint, CheckpointFile, CHECKPOINT_SUFFIX};
+pub use client::{
+    build_client, mint_installation_token, read_token_file, validate_token, ClientOptions,
+    GithubApp, DEFAULT_USER_AGENT,
+};
+pub use config::{load_config, Config, DEFAULT_CONFIG_FILE};
+pub use diff::{diff_forks, load_previous_report, ForkChange, ForkDiff, PreviousFork};
+pub use divergence::{compute_divergence, upstream_default_branch};
+pub use error::{exit_code, ForkliftError};
+pub use exec::{exec_per_fork, ExecSummary};
+pub use fetch::{
+    fetch_all_forks, fetch_page_with_retry, fork_page_count, server_advised_wait, FetchOptions,
+    FetchedForks, ProgressSink, RetryPolicy, MAX_PER_PAGE,
+};
+pub use filter::{
+    filter_forks, filter_records, load_expected_owners, load_ignore_file, mark_unexpected,
+    parse_name_pattern, parse_since, sample_forks, sort_forks, FilterOptions, FilteredForks,
+    SortKey, DEFAULT_IGNORE_FILE,
+};
+pub use graphql::{enterprise_graphql_base, fetch_fork_records_graphql};
+pub use parent::retain_direct_forks;
+pub use post::PostSink;
+pub use pulls::count_open_prs;
+pub use repo::{parse_base_url, parse_github_url, RepoInfo, ENTERPRISE_API_PATH, GITHUB_HOST};
+pub use report::{
+    org_fork_count, render_results, write_results, Column, ForkRecord, NdjsonWriter, OutputFormat,
+    ReportOptions, UpstreamSummary, UrlKind, NO_LICENSE, STDOUT_PATH,
+};
+pub use s3::{S3Location, S3Sink, S3_SCHEME};
+pub use stream::ForkStream;
+pub use summary::{
+    render_divergence_histogram, render_org_stars, render_summary, HIGH_STAR_THRESHOLD,
+};
+pub use tree::{fetch_descendant_forks, DescendantForks};
@@ -0,0 +1,74 @@
+use octocrab::Octocrab;
+use serde::Deserialize;
+use std::sync::Arc;
+use tokio::{sync::Semaphore, task::JoinSet};
+use tracing::{debug, info};
+
+use crate::{ForkRecord, ForkliftError};
+
+/// The subset of a repository's details we care about: what it was forked from
+#[derive(Debug, Deserialize)]
+struct ForkDetail {
+    parent: Option<ParentRepo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ParentRepo {
+    full_name: String,
+}
+
+/// Drop forks whose direct parent isn't `owner/repo`, i.e. forks made from
+/// another fork. Each fork's details are fetched (one request per fork,
+/// holding one of `permits`); forks whose details can't be fetched are kept.
+/// Returns how many forks were dropped.
+pub async fn retain_direct_forks(
+    octocrab: &Octocrab,
+    owner: &str,
+    repo: &str,
+    forks: &mut Vec<ForkRecord>,
+    permits: &Arc<Semaphore>,
+) -> Result<usize, ForkliftError> {
+    info!(
+        "Checking the parent of {} forks of {}/{}",
+        forks.len(),
+        owner,
+        repo
+    );
+    let upstream = format!("{}/{}", owner, repo);
+
+    let mut tasks = JoinSet::new();
+    for (index, fork) in forks.iter().enumerate() {
+        let octo = octocrab.clone();
+        let route = format!("/repos/{}/{}", fork.organization, fork.fork_name);
+        let label = format!("{}/{}", fork.organization, fork.fork_name);
+        let permit = permits.clone().acquire_owned().await.unwrap();
+
+        tasks.spawn(async move {
+            let _permit = permit;
+            let result: Result<ForkDetail, octocrab::Error> = octo.get(route, None::<&()>).await;
+            if let Err(e) = &result {
+                debug!("Could not look up the parent of {}: {}", label, e);
+            }
+            (index, result.ok().and_then(|detail| detail.parent))
+        });
+    }
+
+    let mut indirect = vec![false; forks.len()];
+    while let Some(res) = tasks.join_next().await {
+        let (index, parent) = res?;
+        if let Some(parent) = parent {
+            // GitHub treats owner and repository names case-insensitively
+            if !parent.full_name.eq_ignore_ascii_case(&upstream) {
+                debug!(
+                    "{}/{} is a fork of {}, not of {}",
+                    forks[index].organization, forks[index].fork_name, parent.full_name, upstream
+                );
+                indirect[index] = true;
+            }
+        }
+    }
+
+    let mut flags = indirect.iter();
+    forks.retain(|_| !flags.next().copied().unwrap_or(false));
+    Ok(indirect.iter().filter(|&&dropped| dropped).count())
+}
@@ -0,0 +1,95 @@
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE};
+use tracing::debug;
+
+use crate::client::http_client_builder;
+use crate::report::json_document;
+use crate::{ClientOptions, Column, ForkRecord, ForkliftError};
+
+/// Sends each repository's results as a JSON body to a URL
+#[derive(Clone, Debug)]
+pub struct PostSink {
+    client: reqwest::Client,
+    url: reqwest::Url,
+    headers: HeaderMap,
+}
+
+impl PostSink {
+    /// Validate `url` and the `Name: value` `headers` up front, so a typo
+    /// fails before any forks are fetched. Requests use the timeout,
+    /// `User-Agent`, and proxy of `options`.
+    pub fn new(
+        url: &str,
+        headers: &[String],
+        options: &ClientOptions,
+    ) -> Result<Self, ForkliftError> {
+        let parsed =
+            reqwest::Url::parse(url).map_err(|_| ForkliftError::InvalidPostUrl(url.to_string()))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(ForkliftError::InvalidPostUrl(url.to_string()));
+        }
+
+        let mut header_map = HeaderMap::new();
+        for raw in headers {
+            let invalid = || ForkliftError::InvalidPostHeader(raw.clone());
+            let (name, value) = raw.split_once(':').ok_or_else(invalid)?;
+            let name = HeaderName::from_bytes(name.trim().as_bytes()).map_err(|_| invalid())?;
+            let value = HeaderValue::from_str(value.trim()).map_err(|_| invalid())?;
+            header_map.append(name, value);
+        }
+
+        let client =
+            http_client_builder(options)?
+                .build()
+                .map_err(|e| ForkliftError::PostFailed {
+                    url: url.to_string(),
+                    message: e.to_string(),
+                })?;
+
+        Ok(Self {
+            client,
+            url: parsed,
+            headers: header_map,
+        })
+    }
+
+    /// The URL results are posted to
+    pub fn url(&self) -> &str {
+        self.url.as_str()
+    }
+
+    /// POST `forks` as the same JSON array `--format json` writes, failing on
+    /// any non-2xx response
+    pub async fn post(
+        &self,
+        forks: &[ForkRecord],
+        fields: Option<&[Column]>,
+    ) -> Result<(), ForkliftError> {
+        let failed = |message: String| ForkliftError::PostFailed {
+            url: self.url.to_string(),
+            message,
+        };
+        debug!("Posting {} forks to {}", forks.len(), self.url);
+
+        let response = self
+            .client
+            .post(self.url.clone())
+            .headers(self.headers.clone())
+            .header(CONTENT_TYPE, "application/json")
+            .body(json_document(forks, fields)?)
+            .send()
+            .await
+            .map_err(|e| failed(e.to_string()))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            let body = body.trim();
+            return Err(failed(if body.is_empty() {
+                status.to_string()
+            } else {
+                format!("{}: {}", status, body)
+            }));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,82 @@
+use octocrab::{Octocrab, Page};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::{sync::Semaphore, task::JoinSet};
+use tracing::{debug, info};
+
+use crate::{ForkRecord, ForkliftError};
+
+/// The subset of a pull request we care about: where its head lives
+#[derive(Debug, Deserialize)]
+struct OpenPull {
+    head: PullHead,
+}
+
+#[derive(Debug, Deserialize)]
+struct PullHead {
+    /// Missing when the head repository has since been deleted
+    repo: Option<HeadRepo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HeadRepo {
+    full_name: String,
+}
+
+/// Fill in the number of open pull requests each fork has against upstream.
+/// Upstream's open PRs are listed once (pages fetched in parallel, one of
+/// `permits` per page) and grouped by head repository, so the cost scales
+/// with the number of open PRs rather than the number of forks.
+pub async fn count_open_prs(
+    octocrab: &Octocrab,
+    owner: &str,
+    repo: &str,
+    forks: &mut [ForkRecord],
+    permits: &Arc<Semaphore>,
+) -> Result<(), ForkliftError> {
+    info!("Counting open pull requests against {}/{}", owner, repo);
+
+    let route = |page: u32| {
+        format!(
+            "/repos/{}/{}/pulls?state=open&per_page=100&page={}",
+            owner, repo, page
+        )
+    };
+
+    let mut first: Page<OpenPull> = {
+        let _permit = permits.acquire().await.unwrap();
+        octocrab.get(route(1), None::<&()>).await?
+    };
+    let mut pulls = first.take_items();
+
+    let mut tasks = JoinSet::new();
+    for page in 2..=first.number_of_pages().unwrap_or(1) {
+        let octo = octocrab.clone();
+        let route = route(page);
+        let permit = permits.clone().acquire_owned().await.unwrap();
+        tasks.spawn(async move {
+            let _permit = permit;
+            octo.get::<Page<OpenPull>, _, _>(route, None::<&()>).await
+        });
+    }
+    while let Some(res) = tasks.join_next().await {
+        pulls.extend(res??.take_items());
+    }
+    debug!("Found {} open pull requests", pulls.len());
+
+    // GitHub treats owner and repository names case-insensitively
+    let mut counts: HashMap<String, u64> = HashMap::new();
+    for pull in pulls {
+        if let Some(head) = pull.head.repo {
+            *counts.entry(head.full_name.to_lowercase()).or_default() += 1;
+        }
+    }
+
+    for fork in forks.iter_mut() {
+        let full_name = format!("{}/{}", fork.organization, fork.fork_name).to_lowercase();
+        fork.open_prs = Some(counts.get(&full_name).copied().unwrap_or(0));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,140 @@
+use url::Url;
+
+use crate::ForkliftError;
+
+/// Default public GitHub host, used when no Enterprise base URL is configured
+pub const GITHUB_HOST: &str = "github.com";
+
+/// Path prefix of the REST API on GitHub Enterprise Server
+pub const ENTERPRISE_API_PATH: &str = "/api/v3";
+
+/// Holds the extracted repository info
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepoInfo {
+    pub owner: String,
+    pub name: String,
+}
+
+/// Parse a GitHub URL of the form:
+///   OWNER/REPO
+///   https://HOST/OWNER/REPO
+///   http://HOST/OWNER/REPO
+///   HOST/OWNER/REPO
+///   git@HOST:OWNER/REPO.git
+///   ssh://git@HOST/OWNER/REPO.git
+/// where HOST must match `expected_host` (github.com unless using Enterprise,
+/// and implied by the `OWNER/REPO` shorthand),
+/// ignoring case and a leading `www.`. Anything after OWNER/REPO, such as
+/// `/tree/main`, a query string, or a fragment, is ignored, and a trailing
+/// `.git` is stripped from the repository name.
+/// Returns RepoInfo { owner, name } on success, or ForkliftError otherwise.
+pub fn parse_github_url(raw_url: &str, expected_host: &str) -> Result<RepoInfo, ForkliftError> {
+    let raw_url = raw_url.trim();
+    if let Some(segments) = shorthand_segments(raw_url) {
+        return repo_from_segments(segments);
+    }
+
+    // SCP-like SSH form, as copied from the clone button: git@HOST:OWNER/REPO.git
+    if let Some(rest) = raw_url.strip_prefix("git@") {
+        let (host, path) = rest
+            .split_once(':')
+            .ok_or_else(|| ForkliftError::InvalidUrl(raw_url.to_string()))?;
+        check_host(Some(host), expected_host)?;
+        return repo_from_segments(path.split('/').map(|s| s.to_string()).collect());
+    }
+
+    let parsed = parse_with_default_scheme(raw_url)?;
+    check_host(parsed.host_str(), expected_host)?;
+
+    let segments: Vec<String> = parsed
+        .path_segments()
+        .map(|c| c.map(|s| s.to_string()).collect())
+        .unwrap_or_default();
+
+    repo_from_segments(segments)
+}
+
+/// The owner and name of a bare `OWNER/REPO`. Owners can't contain dots, so
+/// a first segment with one is a host, as in `github.com/OWNER`, not shorthand.
+fn shorthand_segments(raw_url: &str) -> Option<Vec<String>> {
+    let raw_url = raw_url.strip_suffix('/').unwrap_or(raw_url);
+    let (owner, name) = raw_url.split_once('/')?;
+    let plain = |segment: &str| {
+        !segment.is_empty()
+            && segment
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    };
+    (plain(owner) && plain(name) && !owner.contains('.'))
+        .then(|| vec![owner.to_string(), name.to_string()])
+}
+
+/// Ensure a parsed host matches the host we expect repositories to live on;
+/// `www.github.com` serves the same repositories as `github.com`
+fn check_host(host: Option<&str>, expected_host: &str) -> Result<(), ForkliftError> {
+    let matches = host.is_some_and(|host| {
+        let host = host.to_ascii_lowercase();
+        let host = host.strip_prefix("www.").unwrap_or(&host);
+        host.eq_ignore_ascii_case(expected_host)
+    });
+    if !matches {
+        return Err(ForkliftError::InvalidDomain {
+            expected: expected_host.to_string(),
+            found: host.unwrap_or_default().to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Build RepoInfo from URL path segments, ignoring empty segments and
+/// stripping a trailing `.git` from the repository name
+fn repo_from_segments(mut segments: Vec<String>) -> Result<RepoInfo, ForkliftError> {
+    segments.retain(|s| !s.is_empty());
+
+    if segments.len() < 2 {
+        return Err(ForkliftError::InvalidPathSegments(segments));
+    }
+
+    let owner = segments[0].clone();
+    let name = segments[1]
+        .strip_suffix(".git")
+        .unwrap_or(&segments[1])
+        .to_string();
+
+    Ok(RepoInfo { owner, name })
+}
+
+/// Parse a GitHub Enterprise Server base URL such as `https://github.mycorp.com`.
+/// Returns the web host used to validate repository URLs together with the
+/// REST API base URI (`<scheme>://<host>/api/v3`) to hand to Octocrab.
+pub fn parse_base_url(raw_url: &str) -> Result<(String, String), ForkliftError> {
+    let parsed = parse_with_default_scheme(raw_url)?;
+
+    let host = parsed
+        .host_str()
+        .ok_or_else(|| ForkliftError::InvalidUrl(raw_url.to_string()))?
+        .to_string();
+
+    let mut api_base = format!("{}://{}", parsed.scheme(), host);
+    if let Some(port) = parsed.port() {
+        api_base.push_str(&format!(":{}", port));
+    }
+    api_base.push_str(ENTERPRISE_API_PATH);
+
+    Ok((host, api_base))
+}
+
+/// Parse a URL, prepending "https://" when the scheme is missing
+fn parse_with_default_scheme(raw_url: &str) -> Result<Url, ForkliftError> {
+    let url_with_scheme = if ["http://", "https://", "ssh://"].iter().any(|scheme| {
+        raw_url
+            .get(..scheme.len())
+            .is_some_and(|prefix| prefix.eq_ignore_ascii_case(scheme))
+    }) {
+        raw_url.to_string()
+    } else {
+        format!("https://{}", raw_url)
+    };
+
+    Url::parse(&url_with_scheme).map_err(|_| ForkliftError::InvalidUrl(raw_url.to_string()))
+}
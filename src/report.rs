@@ -0,0 +1,1085 @@
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use octocrab::models::Repository;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use crate::{ForkDiff, ForkliftError};
+
+/// Supported report output formats
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    Markdown,
+    Json,
+    Csv,
+    /// Standalone HTML page with a click-to-sort table
+    Html,
+    /// One JSON object per line, streamed as pages arrive when possible
+    Ndjson,
+    /// Prometheus text exposition gauges, e.g. for node_exporter's textfile collector
+    Prometheus,
+    /// Just the fork URLs, one per line, for piping into `xargs` or `git clone`
+    Urls,
+}
+
+/// Which URL of each fork `--format urls` lists
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum UrlKind {
+    /// The fork's page on GitHub
+    #[default]
+    Html,
+    /// The URL to `git clone` the fork from
+    Clone,
+}
+
+impl OutputFormat {
+    /// File extension used when no explicit output path is given
+    pub fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Markdown => "md",
+            OutputFormat::Json => "json",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Html => "html",
+            OutputFormat::Ndjson => "ndjson",
+            OutputFormat::Prometheus => "prom",
+            OutputFormat::Urls => "txt",
+        }
+    }
+
+    /// The format a file extension (without the dot, any case) stands for,
+    /// if it is one Forklift writes
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "md" | "markdown" => Some(OutputFormat::Markdown),
+            "json" => Some(OutputFormat::Json),
+            "csv" => Some(OutputFormat::Csv),
+            "html" | "htm" => Some(OutputFormat::Html),
+            "ndjson" | "jsonl" => Some(OutputFormat::Ndjson),
+            "prom" => Some(OutputFormat::Prometheus),
+            "txt" => Some(OutputFormat::Urls),
+            _ => None,
+        }
+    }
+
+    /// Media type sent along with reports uploaded to S3
+    pub fn media_type(self) -> &'static str {
+        match self {
+            OutputFormat::Markdown => "text/markdown; charset=utf-8",
+            OutputFormat::Json => "application/json",
+            OutputFormat::Csv => "text/csv; charset=utf-8",
+            OutputFormat::Html => "text/html; charset=utf-8",
+            OutputFormat::Ndjson => "application/x-ndjson",
+            OutputFormat::Prometheus => "text/plain; version=0.0.4; charset=utf-8",
+            OutputFormat::Urls => "text/plain; charset=utf-8",
+        }
+    }
+
+    /// Whether reports in this format can be appended to an existing one
+    pub fn supports_append(self) -> bool {
+        matches!(
+            self,
+            OutputFormat::Markdown | OutputFormat::Csv | OutputFormat::Ndjson | OutputFormat::Urls
+        )
+    }
+}
+
+/// Output path that writes the report to stdout instead of a file
+pub const STDOUT_PATH: &str = "-";
+
+/// A single fork as it appears in the report
+#[derive(Debug, Clone, Serialize)]
+pub struct ForkRecord {
+    /// GitHub's repository id, which survives renames and transfers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
+    pub organization: String,
+    pub fork_name: String,
+    pub url: String,
+    pub stars: u32,
+    /// Accounts watching the fork (`subscribers_count`; 0 when GitHub omits it)
+    pub watchers: u32,
+    pub open_issues: u32,
+    /// Forks of the fork itself; a high count marks a downstream hub
+    pub forks: u32,
+    /// Primary language GitHub detected, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    pub owner_type: String,
+    #[serde(skip)]
+    pub updated_at: Option<DateTime<Utc>>,
+    /// Time of the most recent push to any branch of the fork
+    pub pushed_at: Option<DateTime<Utc>>,
+    /// Whether the fork has been archived (made read-only) by its owner
+    pub archived: bool,
+    /// `public`, `private`, or `internal`
+    pub visibility: String,
+    /// The fork's default branch, which may differ from upstream's
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_branch: Option<String>,
+    /// Commits the fork is ahead of upstream, when divergence was computed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ahead: Option<u64>,
+    /// Commits the fork is behind upstream, when divergence was computed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub behind: Option<u64>,
+    /// Open pull requests from the fork against upstream, when counted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub open_prs: Option<u64>,
+    /// Levels below upstream (1 for direct forks), when the fork tree was walked
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub depth: Option<u32>,
+    /// `owner/name` of the repository this fork was made from, when the fork tree was walked
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    /// SPDX id of the license GitHub detected (`NOASSERTION` for unrecognized ones)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    /// Whether `license` differs from upstream's, when upstream's is known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub license_changed: Option<bool>,
+    /// The fork's GitHub topics, when they were fetched
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub topics: Option<Vec<String>>,
+    /// Whether the owner of this organization fork is missing from the
+    /// `--expected` list, when one was given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unexpected: Option<bool>,
+}
+
+impl ForkRecord {
+    /// The URL to `git clone` the fork from, which GitHub derives from its page
+    pub fn clone_url(&self) -> String {
+        format!("{}.git", self.url)
+    }
+}
+
+/// Controls how a report is rendered
+#[derive(Clone, Debug)]
+pub struct ReportOptions {
+    pub format: OutputFormat,
+    /// Add an Owner Type column (set when user forks are included)
+    pub include_users: bool,
+    /// Add a Visibility column (set when private forks are included)
+    pub include_private: bool,
+    /// Add Ahead/Behind columns (set when divergence was computed)
+    pub with_divergence: bool,
+    /// Add an Open PRs column (set when open pull requests were counted)
+    pub with_open_prs: bool,
+    /// Add Depth and Parent columns (set when forks of forks were listed)
+    pub with_depth: bool,
+    /// Add a License column
+    pub with_license: bool,
+    /// Add a Topics column
+    pub with_topics: bool,
+    /// Write a `## <org>` section with its own table per organization (Markdown only)
+    pub group_by_org: bool,
+    /// Number of forks before `--top` truncation, noted in the totals footer
+    pub top_of: Option<usize>,
+    /// Set when fetching stopped at `--max-forks <N>`; the totals footer then
+    /// notes that the report is a sample of the first N forks
+    pub sampled_from: Option<usize>,
+    /// Number of forks a `--sample` was drawn from, and its seed, noted in the
+    /// totals footer so the sample can be reproduced
+    pub random_sample_of: Option<(usize, u64)>,
+    /// The `--timeout-total` budget ran out while fetching; Markdown and HTML
+    /// reports then say they are incomplete
+    pub timed_out: bool,
+    /// Pages that could not be fetched; Markdown reports call them out at the top
+    pub failed_pages: Vec<u32>,
+    /// Start CSV reports with a UTF-8 byte order mark for Excel
+    pub csv_bom: bool,
+    /// Which URL `--format urls` lists
+    pub url_kind: UrlKind,
+    /// Exact columns to write, in order, instead of those implied by the
+    /// options above
+    pub fields: Option<Vec<Column>>,
+    /// Append to the output file rather than truncating it
+    pub append: bool,
+    /// Changes since an earlier report; Markdown and HTML reports end with them
+    pub diff: Option<ForkDiff>,
+    /// Forks listed before filtering, exported by Prometheus reports
+    pub total_forks: Option<usize>,
+    /// Organization forks that passed the filters, before `--sample` and
+    /// `--top` cut them; Prometheus reports count `forks` when unset
+    pub org_forks: Option<usize>,
+    /// The upstream repository itself, described below the Markdown and HTML title
+    pub upstream: Option<UpstreamSummary>,
+}
+
+/// What the report says about the upstream repository
+#[derive(Clone, Debug, Default)]
+pub struct UpstreamSummary {
+    pub stars: u32,
+    /// Every fork GitHub counts, including ones outside this report
+    pub forks: u32,
+    pub description: Option<String>,
+    /// SPDX id of upstream's license, if GitHub detected one
+    pub license: Option<String>,
+}
+
+impl From<&Repository> for UpstreamSummary {
+    fn from(repo: &Repository) -> Self {
+        Self {
+            stars: repo.stargazers_count.unwrap_or(0),
+            forks: repo.forks_count.unwrap_or(0),
+            description: repo
+                .description
+                .as_deref()
+                .map(|d| d.split_whitespace().collect::<Vec<_>>().join(" "))
+                .filter(|d| !d.is_empty()),
+            license: repo.license.as_ref().map(|license| license.spdx_id.clone()),
+        }
+    }
+}
+
+impl UpstreamSummary {
+    /// e.g. `Upstream: 110000 stars, 40000 forks, Apache-2.0 license`
+    fn counts_line(&self) -> String {
+        let mut line = format!("Upstream: {} stars, {} forks", self.stars, self.forks);
+        if let Some(license) = &self.license {
+            line.push_str(&format!(", {} license", license));
+        }
+        line
+    }
+}
+
+impl Default for ReportOptions {
+    fn default() -> Self {
+        Self {
+            format: OutputFormat::Markdown,
+            include_users: false,
+            include_private: false,
+            with_divergence: false,
+            with_open_prs: false,
+            with_license: false,
+            with_topics: false,
+            with_depth: false,
+            group_by_org: false,
+            top_of: None,
+            sampled_from: None,
+            random_sample_of: None,
+            timed_out: false,
+            failed_pages: Vec::new(),
+            csv_bom: false,
+            url_kind: UrlKind::Html,
+            fields: None,
+            append: false,
+            diff: None,
+            total_forks: None,
+            org_forks: None,
+            upstream: None,
+        }
+    }
+}
+
+/// A report column (or JSON key), selectable with `--fields` by its
+/// CSV/JSON name
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Column {
+    Id,
+    Organization,
+    #[value(name = "fork_name")]
+    ForkName,
+    Url,
+    Stars,
+    Watchers,
+    #[value(name = "open_issues")]
+    OpenIssues,
+    Forks,
+    #[value(name = "pushed_at")]
+    LastPush,
+    Archived,
+    #[value(name = "default_branch")]
+    DefaultBranch,
+    Language,
+    #[value(name = "owner_type")]
+    OwnerType,
+    Visibility,
+    Ahead,
+    Behind,
+    #[value(name = "open_prs")]
+    OpenPrs,
+    Depth,
+    Parent,
+    License,
+    Topics,
+}
+
+impl Column {
+    /// Columns enabled by the given report options, in output order
+    fn for_options(options: &ReportOptions) -> Vec<Column> {
+        if let Some(fields) = &options.fields {
+            return fields.clone();
+        }
+        let mut columns = vec![
+            Column::Organization,
+            Column::ForkName,
+            Column::Url,
+            Column::Stars,
+            Column::LastPush,
+            Column::Archived,
+            Column::DefaultBranch,
+            Column::Language,
+        ];
+        if options.include_users {
+            columns.push(Column::OwnerType);
+        }
+        if options.include_private {
+            columns.push(Column::Visibility);
+        }
+        if options.with_divergence {
+            columns.extend([Column::Ahead, Column::Behind]);
+        }
+        if options.with_open_prs {
+            columns.push(Column::OpenPrs);
+        }
+        if options.with_depth {
+            columns.extend([Column::Depth, Column::Parent]);
+        }
+        if options.with_license {
+            columns.push(Column::License);
+        }
+        if options.with_topics {
+            columns.push(Column::Topics);
+        }
+        columns
+    }
+
+    /// Markdown table heading
+    fn title(self, options: &ReportOptions) -> &'static str {
+        match self {
+            Column::Id => "ID",
+            Column::Organization if options.include_users => "Owner",
+            Column::Organization => "Organization",
+            Column::ForkName => "Fork Name",
+            Column::Url => "URL",
+            Column::Stars => "Stars",
+            Column::Watchers => "Watchers",
+            Column::OpenIssues => "Open Issues",
+            Column::Forks => "Forks",
+            Column::LastPush => "Last Push",
+            Column::Archived => "Archived",
+            Column::DefaultBranch => "Default Branch",
+            Column::Language => "Language",
+            Column::OwnerType => "Owner Type",
+            Column::Visibility => "Visibility",
+            Column::Ahead => "Ahead",
+            Column::Behind => "Behind",
+            Column::OpenPrs => "Open PRs",
+            Column::Depth => "Depth",
+            Column::Parent => "Parent",
+            Column::License => "License",
+            Column::Topics => "Topics",
+        }
+    }
+
+    /// CSV header name, matching the JSON keys
+    fn key(self) -> &'static str {
+        match self {
+            Column::Id => "id",
+            Column::Organization => "organization",
+            Column::ForkName => "fork_name",
+            Column::Url => "url",
+            Column::Stars => "stars",
+            Column::Watchers => "watchers",
+            Column::OpenIssues => "open_issues",
+            Column::Forks => "forks",
+            Column::LastPush => "pushed_at",
+            Column::Archived => "archived",
+            Column::DefaultBranch => "default_branch",
+            Column::Language => "language",
+            Column::OwnerType => "owner_type",
+            Column::Visibility => "visibility",
+            Column::Ahead => "ahead",
+            Column::Behind => "behind",
+            Column::OpenPrs => "open_prs",
+            Column::Depth => "depth",
+            Column::Parent => "parent",
+            Column::License => "license",
+            Column::Topics => "topics",
+        }
+    }
+
+    /// Cell value for a fork; unknown divergence or push dates render as `-`
+    fn value(self, fork: &ForkRecord) -> String {
+        let or_dash = |n: Option<u64>| n.map_or_else(|| "-".to_string(), |n| n.to_string());
+        match self {
+            Column::Id => or_dash(fork.id),
+            Column::Organization => fork.organization.clone(),
+            Column::ForkName => fork.fork_name.clone(),
+            Column::Url => fork.url.clone(),
+            Column::Stars => fork.stars.to_string(),
+            Column::Watchers => fork.watchers.to_string(),
+            Column::OpenIssues => fork.open_issues.to_string(),
+            Column::Forks => fork.forks.to_string(),
+            Column::LastPush => fork.pushed_at.map_or_else(
+                || "-".to_string(),
+                |pushed| pushed.format("%Y-%m-%d").to_string(),
+            ),
+            Column::Archived => if fork.archived { "yes" } else { "no" }.to_string(),
+            Column::DefaultBranch => fork
+                .default_branch
+                .clone()
+                .unwrap_or_else(|| "-".to_string()),
+            Column::Language => fork.language.clone().unwrap_or_else(|| "-".to_string()),
+            Column::OwnerType => fork.owner_type.clone(),
+            Column::Visibility => fork.visibility.clone(),
+            Column::Ahead => or_dash(fork.ahead),
+            Column::Behind => or_dash(fork.behind),
+            Column::OpenPrs => or_dash(fork.open_prs),
+            Column::Depth => or_dash(fork.depth.map(u64::from)),
+            Column::Parent => fork.parent.clone().unwrap_or_else(|| "-".to_string()),
+            Column::License => {
+                let license = fork.license.as_deref().unwrap_or(NO_LICENSE);
+                if fork.license_changed == Some(true) {
+                    format!("{} (changed)", license)
+                } else {
+                    license.to_string()
+                }
+            }
+            Column::Topics => match fork.topics.as_deref() {
+                Some(topics) if !topics.is_empty() => topics.join(", "),
+                _ => "-".to_string(),
+            },
+        }
+    }
+}
+
+/// Owners of the forks marked unexpected, sorted and without duplicates,
+/// or `None` when there are none
+fn unexpected_owners(forks: &[ForkRecord]) -> Option<Vec<&str>> {
+    let owners: BTreeSet<&str> = forks
+        .iter()
+        .filter(|fork| fork.unexpected == Some(true))
+        .map(|fork| fork.organization.as_str())
+        .collect();
+    (!owners.is_empty()).then(|| owners.into_iter().collect())
+}
+
+/// License column value of forks without a detected license
+pub const NO_LICENSE: &str = "NONE";
+
+/// Called out at the top of reports cut short by `--timeout-total`
+const TIMED_OUT_NOTE: &str =
+    "this report is incomplete; the --timeout-total budget ran out before every fork page was fetched";
+
+/// Footer summarizing how many forks (and how many of them archived) and
+/// distinct owners the report lists
+fn totals_line(forks: &[ForkRecord], options: &ReportOptions) -> String {
+    let owners: HashSet<&str> = forks.iter().map(|f| f.organization.as_str()).collect();
+    let line = if options.include_users {
+        format!(
+            "Total: {} forks across {} distinct owners",
+            forks.len(),
+            owners.len()
+        )
+    } else {
+        format!(
+            "Total: {} organization forks across {} distinct organizations",
+            forks.len(),
+            owners.len()
+        )
+    };
+    let line = match forks.iter().filter(|f| f.archived).count() {
+        0 => line,
+        archived => format!("{} ({} archived)", line, archived),
+    };
+    let line = match options.top_of {
+        Some(total) => format!("{} (showing top {} of {})", line, forks.len(), total),
+        None => line,
+    };
+    let line = match options.random_sample_of {
+        Some((total, seed)) => {
+            format!("{} (random sample of {} forks, seed {})", line, total, seed)
+        }
+        None => line,
+    };
+    match options.sampled_from {
+        Some(max) => format!(
+            "{} (partial sample: fetching stopped after the first {} forks)",
+            line, max
+        ),
+        None => line,
+    }
+}
+
+/// Fork counts per primary language, most common first, e.g.
+/// `Languages: Go 12, Rust 5, none 1`; `None` when there are no forks
+fn languages_line(forks: &[ForkRecord]) -> Option<String> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for fork in forks {
+        *counts
+            .entry(fork.language.as_deref().unwrap_or("none"))
+            .or_default() += 1;
+    }
+    let mut counts: Vec<(&str, usize)> = counts.into_iter().collect();
+    counts.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)));
+    let counts: Vec<String> = counts
+        .iter()
+        .map(|(language, count)| format!("{} {}", language, count))
+        .collect();
+    (!counts.is_empty()).then(|| format!("Languages: {}", counts.join(", ")))
+}
+
+/// Write results asynchronously in the requested format, either to the file
+/// at `path` or to stdout when `path` is [`STDOUT_PATH`]. With `append`, a
+/// non-empty file is extended: Markdown adds a `## owner/repo` section in
+/// place of the title and CSV leaves out the header row.
+pub async fn write_results(
+    path: &str,
+    owner: &str,
+    repo: &str,
+    forks: &[ForkRecord],
+    options: &ReportOptions,
+) -> Result<(), ForkliftError> {
+    let (mut out, continuing) = open_output(path, options.append).await?;
+    write_report(&mut out, owner, repo, forks, options, continuing).await
+}
+
+/// Render the report in memory instead, for destinations that aren't files
+pub async fn render_results(
+    owner: &str,
+    repo: &str,
+    forks: &[ForkRecord],
+    options: &ReportOptions,
+) -> Result<Vec<u8>, ForkliftError> {
+    let mut out = Vec::new();
+    write_report(&mut out, owner, repo, forks, options, false).await?;
+    Ok(out)
+}
+
+type Output = Box<dyn AsyncWrite + Unpin + Send>;
+
+/// Open `path` (or stdout for [`STDOUT_PATH`]) for writing, truncating it
+/// unless `append` is set. Also returns whether the output continues an
+/// earlier, non-empty report.
+async fn open_output(path: &str, append: bool) -> Result<(Output, bool), ForkliftError> {
+    if path == STDOUT_PATH {
+        Ok((Box::new(tokio::io::stdout()), false))
+    } else if append {
+        let file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await?;
+        let continuing = file.metadata().await?.len() > 0;
+        Ok((Box::new(file), continuing))
+    } else {
+        Ok((Box::new(tokio::fs::File::create(path).await?), false))
+    }
+}
+
+/// Writes NDJSON records as they become available, for streaming forks to
+/// the report while later pages are still being fetched
+pub struct NdjsonWriter {
+    out: Output,
+    fields: Option<Vec<Column>>,
+}
+
+impl NdjsonWriter {
+    /// Open `path` (or stdout for [`STDOUT_PATH`]), appending when `append` is set
+    pub async fn create(
+        path: &str,
+        append: bool,
+        fields: Option<Vec<Column>>,
+    ) -> Result<Self, ForkliftError> {
+        let (out, _) = open_output(path, append).await?;
+        Ok(Self { out, fields })
+    }
+
+    /// Write one line per record
+    pub async fn write(&mut self, forks: &[ForkRecord]) -> Result<(), ForkliftError> {
+        let lines = json_lines(forks, self.fields.as_deref())?;
+        self.out.write_all(&lines).await?;
+        Ok(())
+    }
+
+    /// Flush everything written so far
+    pub async fn finish(mut self) -> Result<(), ForkliftError> {
+        self.out.flush().await?;
+        Ok(())
+    }
+}
+
+/// Write the report body to any async writer and flush it; `continuing`
+/// means it follows an earlier report in the same file
+async fn write_report<W: AsyncWrite + Unpin>(
+    out: &mut W,
+    owner: &str,
+    repo: &str,
+    forks: &[ForkRecord],
+    options: &ReportOptions,
+    continuing: bool,
+) -> Result<(), ForkliftError> {
+    match options.format {
+        OutputFormat::Markdown => {
+            write_markdown(out, owner, repo, forks, options, continuing).await?
+        }
+        OutputFormat::Json => write_json(out, forks, options).await?,
+        OutputFormat::Csv => write_csv(out, forks, options, continuing).await?,
+        OutputFormat::Html => write_html(out, owner, repo, forks, options).await?,
+        OutputFormat::Ndjson => {
+            let lines = json_lines(forks, options.fields.as_deref())?;
+            out.write_all(&lines).await?
+        }
+        OutputFormat::Prometheus => {
+            let metrics = prometheus_metrics(owner, repo, forks, options);
+            out.write_all(metrics.as_bytes()).await?
+        }
+        OutputFormat::Urls => {
+            let urls: String = forks
+                .iter()
+                .map(|fork| match options.url_kind {
+                    UrlKind::Html => format!("{}\n", fork.url),
+                    UrlKind::Clone => format!("{}\n", fork.clone_url()),
+                })
+                .collect();
+            out.write_all(urls.as_bytes()).await?
+        }
+    }
+
+    out.flush().await?;
+    Ok(())
+}
+
+/// Organization-owned forks among `forks`
+pub fn org_fork_count(forks: &[ForkRecord]) -> usize {
+    forks
+        .iter()
+        .filter(|fork| fork.owner_type == "Organization")
+        .count()
+}
+
+/// Gauges for `owner/repo` in the Prometheus text format: every fork the API
+/// listed (when known) and the organization-owned forks in the report
+fn prometheus_metrics(
+    owner: &str,
+    repo: &str,
+    forks: &[ForkRecord],
+    options: &ReportOptions,
+) -> String {
+    let label = format!("{}/{}", owner, repo)
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n");
+    let org_forks = options.org_forks.unwrap_or_else(|| org_fork_count(forks));
+
+    let mut metrics = String::new();
+    let mut gauge = |name: &str, help: &str, value: usize| {
+        metrics.push_str(&format!(
+            "# HELP {0} {1}\n# TYPE {0} gauge\n{0}{{repo=\"{2}\"}} {3}\n",
+            name, help, label, value
+        ));
+    };
+    if let Some(total) = options.total_forks {
+        gauge(
+            "forklift_total_forks",
+            "Forks of the repository listed by GitHub, before filtering.",
+            total,
+        );
+    }
+    gauge(
+        "forklift_org_forks",
+        "Organization-owned forks of the repository that passed the filters.",
+        org_forks,
+    );
+    metrics
+}
+
+/// Write results as a Markdown table
+async fn write_markdown<W: AsyncWrite + Unpin>(
+    file: &mut W,
+    owner: &str,
+    repo: &str,
+    forks: &[ForkRecord],
+    options: &ReportOptions,
+    continuing: bool,
+) -> Result<(), std::io::Error> {
+    let title = if continuing {
+        format!("\n## {}/{}\n\n", owner, repo)
+    } else if options.include_users {
+        format!("# Forks for {}/{}\n\n", owner, repo)
+    } else {
+        format!("# Organization-owned forks for {}/{}\n\n", owner, repo)
+    };
+    file.write_all(title.as_bytes()).await?;
+
+    if let Some(upstream) = &options.upstream {
+        if let Some(description) = &upstream.description {
+            file.write_all(format!("> {}\n\n", description).as_bytes())
+                .await?;
+        }
+        file.write_all(format!("{}\n\n", upstream.counts_line()).as_bytes())
+            .await?;
+    }
+
+    if !options.failed_pages.is_empty() {
+        let pages: Vec<String> = options.failed_pages.iter().map(u32::to_string).collect();
+        let note = format!(
+            "> **Note:** this report is incomplete; these fork pages could not be fetched: {}.\n\n",
+            pages.join(", ")
+        );
+        file.write_all(note.as_bytes()).await?;
+    }
+    if options.timed_out {
+        file.write_all(format!("> **Note:** {}.\n\n", TIMED_OUT_NOTE).as_bytes())
+            .await?;
+    }
+    if let Some(owners) = unexpected_owners(forks) {
+        let owners: Vec<String> = owners.iter().map(|owner| escape_markdown(owner)).collect();
+        let note = format!(
+            "> **Unexpected:** {} {} not in the --expected list (shown in bold).\n\n",
+            owners.join(", "),
+            if owners.len() == 1 { "is" } else { "are" }
+        );
+        file.write_all(note.as_bytes()).await?;
+    }
+
+    if options.group_by_org {
+        for (org, group) in group_by_org(forks) {
+            file.write_all(format!("## {}\n\n", escape_markdown(org)).as_bytes())
+                .await?;
+            write_markdown_table(file, &group, options).await?;
+            file.write_all(b"\n").await?;
+        }
+    } else {
+        let rows: Vec<&ForkRecord> = forks.iter().collect();
+        write_markdown_table(file, &rows, options).await?;
+        file.write_all(b"\n").await?;
+    }
+
+    file.write_all(format!("{}\n", totals_line(forks, options)).as_bytes())
+        .await?;
+    if let Some(languages) = languages_line(forks) {
+        file.write_all(format!("\n{}\n", languages).as_bytes())
+            .await?;
+    }
+
+    if let Some(diff) = &options.diff {
+        let mut section = format!(
+            "\n## Changes since {}\n\n{}\n",
+            diff.previous,
+            diff.counts()
+        );
+        let lines = diff.lines();
+        if !lines.is_empty() {
+            section.push_str(&format!("\n```diff\n{}\n```\n", lines.join("\n")));
+        }
+        file.write_all(section.as_bytes()).await?;
+    }
+
+    Ok(())
+}
+
+/// Split forks into per-organization groups, ordered by each organization's
+/// first appearance so the chosen sort carries over to groups and their rows
+fn group_by_org(forks: &[ForkRecord]) -> Vec<(&str, Vec<&ForkRecord>)> {
+    let mut groups: Vec<(&str, Vec<&ForkRecord>)> = Vec::new();
+    let mut index: HashMap<&str, usize> = HashMap::new();
+    for fork in forks {
+        let org = fork.organization.as_str();
+        let slot = *index.entry(org).or_insert_with(|| {
+            groups.push((org, Vec::new()));
+            groups.len() - 1
+        });
+        groups[slot].1.push(fork);
+    }
+    groups
+}
+
+/// Write one Markdown table (header, separator, and a row per fork)
+async fn write_markdown_table<W: AsyncWrite + Unpin>(
+    file: &mut W,
+    forks: &[&ForkRecord],
+    options: &ReportOptions,
+) -> Result<(), std::io::Error> {
+    let columns = Column::for_options(options);
+    let titles: Vec<&str> = columns.iter().map(|c| c.title(options)).collect();
+    let separators: Vec<String> = titles.iter().map(|t| "-".repeat(t.len() + 2)).collect();
+    file.write_all(format!("| {} |\n", titles.join(" | ")).as_bytes())
+        .await?;
+    file.write_all(format!("|{}|\n", separators.join("|")).as_bytes())
+        .await?;
+
+    for fork in forks {
+        let cells: Vec<String> = columns
+            .iter()
+            .map(|&c| {
+                let value = c.value(fork);
+                // Escaping would break the autolink, and only a pipe can end the cell early
+                if c == Column::Url {
+                    value.replace('|', "\\|")
+                } else if c == Column::Organization && fork.unexpected == Some(true) {
+                    format!("**{}**", escape_markdown(&value))
+                } else {
+                    escape_markdown(&value)
+                }
+            })
+            .collect();
+        file.write_all(format!("| {} |\n", cells.join(" | ")).as_bytes())
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Backslash-escape the characters that would end a table cell (`|`) or
+/// apply inline formatting, and flatten line breaks that would end the row
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | '|' | '`' | '*' | '_' | '~' | '[' | ']' | '<' | '>' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' | '\r' => escaped.push(' '),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// A fork projected onto the `--fields` columns, serialized in that order
+struct SelectedFields(Vec<(&'static str, serde_json::Value)>);
+
+impl Serialize for SelectedFields {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (key, value) in &self.0 {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+fn select_fields(fork: &ForkRecord, fields: &[Column]) -> Result<SelectedFields, ForkliftError> {
+    let mut record = serde_json::to_value(fork)?;
+    let entries = fields
+        .iter()
+        .map(|field| (field.key(), record[field.key()].take()))
+        .collect();
+    Ok(SelectedFields(entries))
+}
+
+/// Serialize each fork as one compact JSON line, projected onto `fields` when given
+fn json_lines(forks: &[ForkRecord], fields: Option<&[Column]>) -> Result<Vec<u8>, ForkliftError> {
+    let mut lines = Vec::new();
+    for fork in forks {
+        match fields {
+            Some(fields) => serde_json::to_writer(&mut lines, &select_fields(fork, fields)?)?,
+            None => serde_json::to_writer(&mut lines, fork)?,
+        }
+        lines.push(b'\n');
+    }
+    Ok(lines)
+}
+
+/// Write results as a JSON array of objects (`[]` when there are no forks);
+/// with `--fields`, each object has exactly those keys
+async fn write_json<W: AsyncWrite + Unpin>(
+    file: &mut W,
+    forks: &[ForkRecord],
+    options: &ReportOptions,
+) -> Result<(), ForkliftError> {
+    let mut body = json_document(forks, options.fields.as_deref())?;
+    body.push(b'\n');
+    file.write_all(&body).await?;
+    Ok(())
+}
+
+/// The pretty-printed JSON array of a JSON report, projected onto `fields` when given
+pub(crate) fn json_document(
+    forks: &[ForkRecord],
+    fields: Option<&[Column]>,
+) -> Result<Vec<u8>, ForkliftError> {
+    Ok(match fields {
+        Some(fields) => {
+            let selected = forks
+                .iter()
+                .map(|fork| select_fields(fork, fields))
+                .collect::<Result<Vec<_>, _>>()?;
+            serde_json::to_vec_pretty(&selected)?
+        }
+        None => serde_json::to_vec_pretty(forks)?,
+    })
+}
+
+/// UTF-8 byte order mark; Excel needs it to read non-ASCII text correctly
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Write results as CSV with a header row (unless continuing an earlier
+/// report), quoting fields as needed
+async fn write_csv<W: AsyncWrite + Unpin>(
+    file: &mut W,
+    forks: &[ForkRecord],
+    options: &ReportOptions,
+    continuing: bool,
+) -> Result<(), std::io::Error> {
+    let columns = Column::for_options(options);
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    if !continuing {
+        writer.write_record(columns.iter().map(|c| c.key()))?;
+    }
+    for fork in forks {
+        writer.write_record(columns.iter().map(|c| c.value(fork)))?;
+    }
+    let body = writer.into_inner().map_err(|e| e.into_error())?;
+
+    if options.csv_bom && !continuing {
+        file.write_all(UTF8_BOM).await?;
+    }
+    file.write_all(&body).await
+}
+
+/// Click a column heading to sort by it; click again to reverse. Cells that
+/// parse as numbers compare numerically, everything else as text.
+const SORT_SCRIPT: &str = r#"<script>
+document.querySelectorAll("th").forEach((th, column) => {
+  th.addEventListener("click", () => {
+    const body = th.closest("table").tBodies[0];
+    const ascending = th.dataset.order !== "asc";
+    th.closest("tr").querySelectorAll("th").forEach((other) => delete other.dataset.order);
+    th.dataset.order = ascending ? "asc" : "desc";
+    const value = (row) => row.cells[column].textContent.trim();
+    const rows = Array.from(body.rows).sort((a, b) => {
+      const [x, y] = [value(a), value(b)];
+      const [nx, ny] = [Number(x), Number(y)];
+      const order = x !== "" && y !== "" && !isNaN(nx) && !isNaN(ny) ? nx - ny : x.localeCompare(y);
+      return ascending ? order : -order;
+    });
+    rows.forEach((row) => body.appendChild(row));
+  });
+});
+</script>"#;
+
+/// Escape text for safe inclusion in HTML element content and attributes
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Write results as a standalone HTML document with a sortable table
+async fn write_html<W: AsyncWrite + Unpin>(
+    file: &mut W,
+    owner: &str,
+    repo: &str,
+    forks: &[ForkRecord],
+    options: &ReportOptions,
+) -> Result<(), std::io::Error> {
+    let heading = if options.include_users {
+        format!("Forks for {}/{}", owner, repo)
+    } else {
+        format!("Organization-owned forks for {}/{}", owner, repo)
+    };
+    let heading = escape_html(&heading);
+    let generated = Utc::now().format("%Y-%m-%d %H:%M:%S UTC");
+
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{heading}</title>\n<style>\n\
+         body {{ font-family: sans-serif; margin: 2rem; }}\n\
+         table {{ border-collapse: collapse; }}\n\
+         th, td {{ border: 1px solid #ccc; padding: 0.3rem 0.6rem; text-align: left; }}\n\
+         th {{ cursor: pointer; background: #f3f3f3; }}\n\
+         th[data-order=\"asc\"]::after {{ content: \" \\25B2\"; }}\n\
+         th[data-order=\"desc\"]::after {{ content: \" \\25BC\"; }}\n\
+         tr.unexpected td {{ background: #fff3cd; }}\n\
+         </style>\n</head>\n<body>\n<h1>{heading}</h1>\n<p>Generated {generated}</p>\n"
+    );
+    if let Some(upstream) = &options.upstream {
+        if let Some(description) = &upstream.description {
+            html.push_str(&format!(
+                "<blockquote>{}</blockquote>\n",
+                escape_html(description)
+            ));
+        }
+        html.push_str(&format!(
+            "<p>{}</p>\n",
+            escape_html(&upstream.counts_line())
+        ));
+    }
+
+    if !options.failed_pages.is_empty() {
+        let pages: Vec<String> = options.failed_pages.iter().map(u32::to_string).collect();
+        html.push_str(&format!(
+            "<p><strong>Note:</strong> this report is incomplete; these fork pages could not be fetched: {}.</p>\n",
+            pages.join(", ")
+        ));
+    }
+    if options.timed_out {
+        html.push_str(&format!(
+            "<p><strong>Note:</strong> {}.</p>\n",
+            TIMED_OUT_NOTE
+        ));
+    }
+    if let Some(owners) = unexpected_owners(forks) {
+        let owners: Vec<String> = owners.iter().map(|owner| escape_html(owner)).collect();
+        html.push_str(&format!(
+            "<p><strong>Unexpected:</strong> {} {} not in the --expected list (highlighted below).</p>\n",
+            owners.join(", "),
+            if owners.len() == 1 { "is" } else { "are" }
+        ));
+    }
+
+    let columns = Column::for_options(options);
+    html.push_str("<table>\n<thead>\n<tr>");
+    for column in &columns {
+        html.push_str(&format!("<th>{}</th>", escape_html(column.title(options))));
+    }
+    html.push_str("</tr>\n</thead>\n<tbody>\n");
+
+    for fork in forks {
+        if fork.unexpected == Some(true) {
+            html.push_str("<tr class=\"unexpected\">");
+        } else {
+            html.push_str("<tr>");
+        }
+        for column in &columns {
+            let value = escape_html(&column.value(fork));
+            if *column == Column::Url {
+                html.push_str(&format!("<td><a href=\"{value}\">{value}</a></td>"));
+            } else {
+                html.push_str(&format!("<td>{value}</td>"));
+            }
+        }
+        html.push_str("</tr>\n");
+    }
+
+    html.push_str("</tbody>\n</table>\n");
+    html.push_str(&format!(
+        "<p>{}</p>\n",
+        escape_html(&totals_line(forks, options))
+    ));
+    if let Some(languages) = languages_line(forks) {
+        html.push_str(&format!("<p>{}</p>\n", escape_html(&languages)));
+    }
+    if let Some(diff) = &options.diff {
+        html.push_str(&format!(
+            "<h2>Changes since {}</h2>\n<p>{}</p>\n",
+            escape_html(&diff.previous),
+            escape_html(&diff.counts())
+        ));
+        let lines = diff.lines();
+        if !lines.is_empty() {
+            html.push_str(&format!("<pre>{}</pre>\n", escape_html(&lines.join("\n"))));
+        }
+    }
+    html.push_str(SORT_SCRIPT);
+    html.push_str("\n</body>\n</html>\n");
+
+    file.write_all(html.as_bytes()).await
+}
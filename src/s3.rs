@@ -0,0 +1,263 @@
+use chrono::Utc;
+use reqwest::header::CONTENT_TYPE;
+use ring::{digest, hmac};
+use std::fmt;
+use tracing::debug;
+
+use crate::client::http_client_builder;
+use crate::{ClientOptions, ForkliftError};
+
+/// Prefix of `--output` values that name an S3 object instead of a file
+pub const S3_SCHEME: &str = "s3://";
+
+/// Region signed for when neither `AWS_REGION` nor `AWS_DEFAULT_REGION` is set
+const DEFAULT_REGION: &str = "us-east-1";
+
+/// An object in an S3-compatible bucket, as written `s3://BUCKET/KEY`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct S3Location {
+    pub bucket: String,
+    pub key: String,
+}
+
+impl S3Location {
+    /// The location `raw` names, or `None` when it isn't an `s3://` URL
+    pub fn parse(raw: &str) -> Result<Option<Self>, ForkliftError> {
+        let Some(rest) = raw.strip_prefix(S3_SCHEME) else {
+            return Ok(None);
+        };
+        match rest.split_once('/') {
+            Some((bucket, key)) if !bucket.is_empty() && !key.is_empty() && !key.ends_with('/') => {
+                Ok(Some(Self {
+                    bucket: bucket.to_string(),
+                    key: key.to_string(),
+                }))
+            }
+            _ => Err(ForkliftError::InvalidS3Url(raw.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for S3Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}/{}", S3_SCHEME, self.bucket, self.key)
+    }
+}
+
+/// Uploads reports to S3 or an S3-compatible store, signing each request
+/// with AWS Signature Version 4
+#[derive(Clone)]
+pub struct S3Sink {
+    client: reqwest::Client,
+    /// `AWS_ENDPOINT_URL_S3` or `AWS_ENDPOINT_URL`, addressed path-style;
+    /// AWS itself when unset
+    endpoint: Option<reqwest::Url>,
+    region: String,
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: Option<String>,
+}
+
+impl fmt::Debug for S3Sink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("S3Sink")
+            .field("endpoint", &self.endpoint)
+            .field("region", &self.region)
+            .field("access_key_id", &self.access_key_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl S3Sink {
+    /// Read the credentials, region, and endpoint from the standard AWS
+    /// environment variables; uploads use the timeout, `User-Agent`, and
+    /// proxy of `options`
+    pub fn from_env(options: &ClientOptions) -> Result<Self, ForkliftError> {
+        let var = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+        let (Some(access_key_id), Some(secret_access_key)) =
+            (var("AWS_ACCESS_KEY_ID"), var("AWS_SECRET_ACCESS_KEY"))
+        else {
+            return Err(ForkliftError::MissingS3Credentials);
+        };
+        let endpoint = var("AWS_ENDPOINT_URL_S3")
+            .or_else(|| var("AWS_ENDPOINT_URL"))
+            .map(|raw| {
+                reqwest::Url::parse(&raw)
+                    .ok()
+                    .filter(|url| matches!(url.scheme(), "http" | "https") && url.has_host())
+                    .ok_or(ForkliftError::InvalidS3Endpoint(raw))
+            })
+            .transpose()?;
+
+        let client =
+            http_client_builder(options)?
+                .build()
+                .map_err(|e| ForkliftError::S3UploadFailed {
+                    location: "S3".to_string(),
+                    message: e.to_string(),
+                })?;
+
+        Ok(Self {
+            client,
+            endpoint,
+            region: var("AWS_REGION")
+                .or_else(|| var("AWS_DEFAULT_REGION"))
+                .unwrap_or_else(|| DEFAULT_REGION.to_string()),
+            access_key_id,
+            secret_access_key,
+            session_token: var("AWS_SESSION_TOKEN"),
+        })
+    }
+
+    /// PUT `body` as the object at `location`, replacing any previous one
+    pub async fn put(
+        &self,
+        location: &S3Location,
+        body: Vec<u8>,
+        content_type: &str,
+    ) -> Result<(), ForkliftError> {
+        let failed = |message: String| ForkliftError::S3UploadFailed {
+            location: location.to_string(),
+            message,
+        };
+        let url = self
+            .object_url(location)
+            .map_err(|e| failed(e.to_string()))?;
+        debug!("Uploading {} bytes to {} ({})", body.len(), location, url);
+
+        let payload_hash = hex(digest::digest(&digest::SHA256, &body).as_ref());
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let host = match url.port() {
+            Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+            None => url.host_str().unwrap_or_default().to_string(),
+        };
+
+        // Canonical headers must be sorted by name
+        let mut headers = vec![
+            ("host", host),
+            ("x-amz-content-sha256", payload_hash.clone()),
+            ("x-amz-date", amz_date.clone()),
+        ];
+        if let Some(token) = &self.session_token {
+            headers.push(("x-amz-security-token", token.clone()));
+        }
+        let signed_headers = headers
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>()
+            .join(";");
+        let canonical_headers: String = headers
+            .iter()
+            .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+            .collect();
+        let canonical_request = format!(
+            "PUT\n{}\n\n{}\n{}\n{}",
+            url.path(),
+            canonical_headers,
+            signed_headers,
+            payload_hash
+        );
+
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex(digest::digest(&digest::SHA256, canonical_request.as_bytes()).as_ref())
+        );
+        let signing_key = [self.region.as_str(), "s3", "aws4_request"].iter().fold(
+            sign(
+                format!("AWS4{}", self.secret_access_key).as_bytes(),
+                date.as_bytes(),
+            ),
+            |key, part| sign(&key, part.as_bytes()),
+        );
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key_id,
+            scope,
+            signed_headers,
+            hex(&sign(&signing_key, string_to_sign.as_bytes()))
+        );
+
+        let mut request = self
+            .client
+            .put(url)
+            .header("authorization", authorization)
+            .header(CONTENT_TYPE, content_type);
+        // reqwest sets the host header itself, from the same URL
+        for (name, value) in headers.into_iter().filter(|(name, _)| *name != "host") {
+            request = request.header(name, value);
+        }
+        let response = request
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| failed(e.to_string()))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(failed(match s3_error_message(&body) {
+                Some(message) => format!("{}: {}", status, message),
+                None => status.to_string(),
+            }));
+        }
+        Ok(())
+    }
+
+    /// Path-style URL on a custom endpoint, or a virtual-hosted one on AWS
+    /// (except for bucket names with dots, which break its TLS certificate)
+    fn object_url(&self, location: &S3Location) -> Result<reqwest::Url, url::ParseError> {
+        let key = encode_path(&location.key);
+        match &self.endpoint {
+            Some(endpoint) => endpoint.join(&format!(
+                "{}/{}/{}",
+                endpoint.path().trim_end_matches('/'),
+                encode_path(&location.bucket),
+                key
+            )),
+            None if location.bucket.contains('.') => reqwest::Url::parse(&format!(
+                "https://s3.{}.amazonaws.com/{}/{}",
+                self.region, location.bucket, key
+            )),
+            None => reqwest::Url::parse(&format!(
+                "https://{}.s3.{}.amazonaws.com/{}",
+                location.bucket, self.region, key
+            )),
+        }
+    }
+}
+
+fn sign(key: &[u8], data: &[u8]) -> Vec<u8> {
+    hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, key), data)
+        .as_ref()
+        .to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Percent-encode everything but unreserved characters and `/`, the way
+/// Signature Version 4 expects object keys in the canonical request (and
+/// GitHub branch names in a URL path)
+pub(crate) fn encode_path(path: &str) -> String {
+    path.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// The `<Message>` of an S3 XML error body
+fn s3_error_message(body: &str) -> Option<&str> {
+    let start = body.find("<Message>")? + "<Message>".len();
+    let end = body[start..].find("</Message>")? + start;
+    Some(body[start..end].trim()).filter(|message| !message.is_empty())
+}
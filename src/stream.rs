@@ -0,0 +1,84 @@
+use octocrab::models::Repository;
+use octocrab::Octocrab;
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+use tokio::task::JoinHandle;
+
+use crate::{fetch_all_forks, FetchOptions, FetchedForks, ForkliftError};
+
+/// The forks of a repository, yielded as their pages arrive instead of
+/// collected into one `Vec`. Pages are fetched in the background by
+/// [`fetch_all_forks`], so only the pages not yet consumed are held in
+/// memory (plus every fork id, to drop forks repeated across pages).
+pub struct ForkStream {
+    pages: UnboundedReceiver<Vec<Repository>>,
+    page: std::vec::IntoIter<Repository>,
+    fetch: Option<JoinHandle<Result<FetchedForks, ForkliftError>>>,
+    outcome: Option<FetchedForks>,
+}
+
+impl ForkStream {
+    /// Start fetching the forks of `owner/repo` with `options`, whose own
+    /// `stream` is replaced. Must be called within a Tokio runtime.
+    pub fn start(
+        octocrab: Octocrab,
+        owner: impl Into<String>,
+        repo: impl Into<String>,
+        options: FetchOptions,
+    ) -> Self {
+        let (owner, repo) = (owner.into(), repo.into());
+        let (sender, pages) = mpsc::unbounded_channel();
+        let options = FetchOptions {
+            stream: Some(sender),
+            ..options
+        };
+        // The sender lives in the task, so the channel closes once the fetch ends
+        let fetch =
+            tokio::spawn(async move { fetch_all_forks(&octocrab, &owner, &repo, &options).await });
+        Self {
+            pages,
+            page: Vec::new().into_iter(),
+            fetch: Some(fetch),
+            outcome: None,
+        }
+    }
+
+    /// The next fork, or `None` once every page was fetched. If fetching
+    /// fails, the error is yielded once before the stream ends.
+    pub async fn next(&mut self) -> Option<Result<Repository, ForkliftError>> {
+        loop {
+            if let Some(fork) = self.page.next() {
+                return Some(Ok(fork));
+            }
+            match self.pages.recv().await {
+                Some(page) => self.page = page.into_iter(),
+                None => {
+                    let fetch = self.fetch.take()?;
+                    return match fetch.await {
+                        Ok(Ok(fetched)) => {
+                            self.outcome = Some(fetched);
+                            None
+                        }
+                        Ok(Err(e)) => Some(Err(e)),
+                        Err(e) => Some(Err(e.into())),
+                    };
+                }
+            }
+        }
+    }
+
+    /// How the fetch went (failed pages, whether it was cut short), once the
+    /// stream has ended without an error. Its `forks` are empty unless a
+    /// checkpoint was kept, as they were all yielded by [`Self::next`].
+    pub fn outcome(&self) -> Option<&FetchedForks> {
+        self.outcome.as_ref()
+    }
+}
+
+impl Drop for ForkStream {
+    /// Stop fetching pages nobody will read
+    fn drop(&mut self) {
+        if let Some(fetch) = &self.fetch {
+            fetch.abort();
+        }
+    }
+}
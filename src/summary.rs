@@ -0,0 +1,175 @@
+use console::Style;
+use std::collections::HashMap;
+
+use crate::ForkRecord;
+
+/// Forks with at least this many stars are highlighted in the summary
+pub const HIGH_STAR_THRESHOLD: u32 = 100;
+
+/// Forks listed under each organization before the rest are elided
+const FORKS_PER_ORG: usize = 5;
+
+/// Width of the longest bar in the divergence histogram
+const HISTOGRAM_WIDTH: usize = 30;
+
+/// Ahead-count buckets of the divergence histogram, as inclusive ranges
+const AHEAD_BUCKETS: [(&str, u64, u64); 4] = [
+    ("0", 0, 0),
+    ("1-10", 1, 10),
+    ("11-100", 11, 100),
+    ("100+", 101, u64::MAX),
+];
+
+/// Render a short terminal summary of the `top` owners with the most forks,
+/// listing each owner's most-starred forks. High-star forks are green and
+/// archived ones dim; colors follow stderr's terminal support and `NO_COLOR`.
+pub fn render_summary(owner: &str, repo: &str, forks: &[ForkRecord], top: usize) -> String {
+    let bold = Style::new().for_stderr().bold();
+    let green = Style::new().for_stderr().green();
+    let dim = Style::new().for_stderr().dim();
+
+    let mut by_owner: HashMap<&str, Vec<&ForkRecord>> = HashMap::new();
+    for fork in forks {
+        by_owner.entry(&fork.organization).or_default().push(fork);
+    }
+    let mut owners: Vec<(&str, Vec<&ForkRecord>)> = by_owner.into_iter().collect();
+    owners.sort_by(|(a, a_forks), (b, b_forks)| b_forks.len().cmp(&a_forks.len()).then(a.cmp(b)));
+
+    let mut out = format!(
+        "{}\n",
+        bold.apply_to(format!("Top owners by fork count for {}/{}", owner, repo))
+    );
+    if owners.is_empty() {
+        out.push_str("  (no forks)\n");
+    }
+    for (login, mut owned) in owners.into_iter().take(top) {
+        let noun = if owned.len() == 1 { "fork" } else { "forks" };
+        out.push_str(&format!(
+            "  {} {}\n",
+            bold.apply_to(login),
+            format_args!("({} {})", owned.len(), noun)
+        ));
+
+        owned.sort_by(|a, b| b.stars.cmp(&a.stars).then(a.fork_name.cmp(&b.fork_name)));
+        for fork in owned.iter().take(FORKS_PER_ORG) {
+            let line = format!("{} ★ {}", fork.fork_name, fork.stars);
+            let line = if fork.archived {
+                dim.apply_to(format!("{} (archived)", line)).to_string()
+            } else if fork.stars >= HIGH_STAR_THRESHOLD {
+                green.apply_to(line).to_string()
+            } else {
+                line
+            };
+            out.push_str(&format!("    {}\n", line));
+        }
+        if owned.len() > FORKS_PER_ORG {
+            out.push_str(&format!(
+                "    {}\n",
+                dim.apply_to(format!("… and {} more", owned.len() - FORKS_PER_ORG))
+            ));
+        }
+    }
+    out
+}
+
+/// Render a table of every owner with its fork count and the stars summed
+/// across those forks, ranked by total stars, for `--summary orgs`
+pub fn render_org_stars(owner: &str, repo: &str, forks: &[ForkRecord]) -> String {
+    let bold = Style::new().for_stderr().bold();
+
+    let mut totals: HashMap<&str, (usize, u64)> = HashMap::new();
+    for fork in forks {
+        let entry = totals.entry(&fork.organization).or_default();
+        entry.0 += 1;
+        entry.1 += u64::from(fork.stars);
+    }
+    let mut owners: Vec<(&str, (usize, u64))> = totals.into_iter().collect();
+    owners.sort_by(|(a, (a_forks, a_stars)), (b, (b_forks, b_stars))| {
+        b_stars
+            .cmp(a_stars)
+            .then(b_forks.cmp(a_forks))
+            .then(a.cmp(b))
+    });
+
+    let mut out = format!(
+        "{}\n",
+        bold.apply_to(format!("Owners by total stars for {}/{}", owner, repo))
+    );
+    if owners.is_empty() {
+        out.push_str("  (no forks)\n");
+        return out;
+    }
+    let width = owners
+        .iter()
+        .map(|(login, _)| login.chars().count())
+        .chain(["Organization".len()])
+        .max()
+        .unwrap_or(0);
+    out.push_str(&format!(
+        "  {}\n",
+        bold.apply_to(format!(
+            "{:<width$}  {:>5}  {:>11}",
+            "Organization", "Forks", "Total Stars"
+        ))
+    ));
+    for (login, (count, stars)) in owners {
+        out.push_str(&format!(
+            "  {:<width$}  {:>5}  {:>11}\n",
+            login, count, stars
+        ));
+    }
+    out
+}
+
+/// Render a text bar chart of how many forks are 0, 1-10, 11-100 and 100+
+/// commits ahead of upstream, from the ahead counts `--with-divergence`
+/// gathered. Forks whose comparison failed are counted separately.
+pub fn render_divergence_histogram(owner: &str, repo: &str, forks: &[ForkRecord]) -> String {
+    let bold = Style::new().for_stderr().bold();
+    let green = Style::new().for_stderr().green();
+    let dim = Style::new().for_stderr().dim();
+
+    let counts: Vec<usize> = AHEAD_BUCKETS
+        .iter()
+        .map(|&(_, low, high)| {
+            forks
+                .iter()
+                .filter(|fork| {
+                    fork.ahead
+                        .is_some_and(|ahead| (low..=high).contains(&ahead))
+                })
+                .count()
+        })
+        .collect();
+    let largest = counts.iter().copied().max().unwrap_or(0).max(1);
+
+    let mut out = format!(
+        "{}\n",
+        bold.apply_to(format!(
+            "Commits ahead of upstream for forks of {}/{}",
+            owner, repo
+        ))
+    );
+    for (&(label, _, _), &count) in AHEAD_BUCKETS.iter().zip(&counts) {
+        // Any non-empty bucket gets at least one block so it stays visible
+        let width = (count * HISTOGRAM_WIDTH).div_ceil(largest);
+        out.push_str(&format!(
+            "  {:>6} │ {} {}\n",
+            label,
+            green.apply_to("█".repeat(width)),
+            count
+        ));
+    }
+    let unknown = forks.iter().filter(|fork| fork.ahead.is_none()).count();
+    if unknown > 0 {
+        out.push_str(&format!(
+            "  {}\n",
+            dim.apply_to(format!(
+                "{} {} could not be compared",
+                unknown,
+                if unknown == 1 { "fork" } else { "forks" }
+            ))
+        ));
+    }
+    out
+}
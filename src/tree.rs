@@ -0,0 +1,162 @@
+use octocrab::models::{Repository, RepositoryId};
+use octocrab::Octocrab;
+use std::collections::HashSet;
+use tokio::task::JoinSet;
+use tokio::time::Instant;
+use tracing::{debug, info, warn};
+
+use crate::filter::to_record;
+use crate::{fetch_all_forks, FetchOptions, ForkRecord, ForkliftError};
+
+/// Forks found below the direct forks of a repository
+#[derive(Debug, Default)]
+pub struct DescendantForks {
+    /// Forks below depth 1, carrying their depth and parent
+    pub records: Vec<ForkRecord>,
+    /// The walk stopped at `max_forks`, counting the direct forks, so the
+    /// tree is only listed in part
+    pub limited: bool,
+    /// The deadline passed during the walk
+    pub timed_out: bool,
+}
+
+/// Walk the fork tree below the direct `forks` of an upstream repository,
+/// listing the forks of each organization fork (or of every fork, with
+/// `include_users`) level by level down to `max_depth`, where the direct
+/// forks are depth 1. Returns the forks found below depth 1 as records
+/// carrying their depth and parent. A fork reachable through several paths
+/// is listed once, at its shallowest depth. `max_forks` caps the whole
+/// tree, direct forks included, and the walk stops once the deadline passes.
+pub async fn fetch_descendant_forks(
+    octocrab: &Octocrab,
+    forks: &[Repository],
+    max_depth: u32,
+    include_users: bool,
+    options: &FetchOptions,
+) -> Result<DescendantForks, ForkliftError> {
+    let mut seen: HashSet<RepositoryId> = forks.iter().map(|fork| fork.id).collect();
+    let mut frontier: Vec<Repository> = forks.to_vec();
+    let mut found = forks.len();
+    let mut descendants = DescendantForks::default();
+    // One bar or log line per parent would flood the terminal, so levels are
+    // logged instead; the page window only applies to upstream's own fork list
+    let options = FetchOptions {
+        show_progress: false,
+        since_page: None,
+        until_page: None,
+        stream: None,
+        checkpoint: None,
+        resume: false,
+        warn_threshold: None,
+        quiet: true,
+        ..options.clone()
+    };
+
+    for depth in 2..=max_depth {
+        let parents: Vec<(String, String)> = frontier
+            .iter()
+            .filter(|fork| fork.forks_count.unwrap_or(0) > 0)
+            .filter_map(|fork| {
+                let owner = fork.owner.as_ref()?;
+                (include_users || owner.r#type == "Organization")
+                    .then(|| (owner.login.clone(), fork.name.clone()))
+            })
+            .collect();
+        if parents.is_empty() {
+            break;
+        }
+        if options
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            descendants.timed_out = true;
+            break;
+        }
+        // Every parent of a level may use up what is left of the cap
+        let budget = options.max_forks.map(|max| max.saturating_sub(found));
+        if budget == Some(0) {
+            descendants.limited = true;
+            break;
+        }
+        let parent_count = parents.len();
+        debug!(
+            "Listing forks of {} depth-{} forks",
+            parent_count,
+            depth - 1
+        );
+
+        let mut tasks = JoinSet::new();
+        for (owner, repo) in parents {
+            let octocrab = octocrab.clone();
+            let options = FetchOptions {
+                max_forks: budget,
+                ..options.clone()
+            };
+            tasks.spawn(async move {
+                let fetched = fetch_all_forks(&octocrab, &owner, &repo, &options).await;
+                (format!("{}/{}", owner, repo), fetched)
+            });
+        }
+
+        let mut next = Vec::new();
+        let mut incomplete = 0;
+        while let Some(res) = tasks.join_next().await {
+            let (parent, fetched) = res?;
+            let fetched = match fetched {
+                Ok(fetched) => fetched,
+                Err(e) if options.keep_partial => {
+                    debug!("Skipping forks of {}: {}", parent, e);
+                    incomplete += 1;
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            if !fetched.failed_pages.is_empty() {
+                debug!(
+                    "Fork pages {:?} of {} failed; continuing with the forks that were fetched",
+                    fetched.failed_pages, parent
+                );
+                incomplete += 1;
+            }
+            descendants.timed_out |= fetched.timed_out;
+            descendants.limited |= fetched.limited;
+            for fork in fetched.forks {
+                // The id set also guards against cycles, should GitHub ever report one
+                if !seen.insert(fork.id) {
+                    continue;
+                }
+                if options.max_forks.is_some_and(|max| found >= max) {
+                    descendants.limited = true;
+                    break;
+                }
+                found += 1;
+                if let Some(mut record) = to_record(fork.clone()) {
+                    record.depth = Some(depth);
+                    record.parent = Some(parent.clone());
+                    descendants.records.push(record);
+                }
+                next.push(fork);
+            }
+        }
+        info!(
+            "Found {} forks at depth {}, listing the forks of {} depth-{} forks",
+            next.len(),
+            depth,
+            parent_count,
+            depth - 1
+        );
+        if incomplete > 0 {
+            warn!(
+                "Could not list every fork of {} depth-{} forks; continuing with the forks that were fetched",
+                incomplete,
+                depth - 1
+            );
+        }
+        if descendants.timed_out || descendants.limited {
+            break;
+        }
+        frontier = next;
+    }
+
+    Ok(descendants)
+}
@@ -0,0 +1,104 @@
+//! The forklift binary run against a fake GitHub API served by wiremock
+
+use serde_json::json;
+use std::path::PathBuf;
+use std::process::Command;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// A scratch directory for one test, so no `forklift.toml` or `.env` of the
+/// checkout is picked up
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("forklift-cli-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Serve an upstream repository without forks under the Enterprise API path
+async fn mount_upstream(server: &MockServer) {
+    Mock::given(method("GET"))
+        .and(path("/api/v3/repos/octo/upstream"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": 1,
+            "name": "upstream",
+            "full_name": "octo/upstream",
+            "url": format!("{}/api/v3/repos/octo/upstream", server.uri()),
+            "forks_count": 0
+        })))
+        .mount(server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/repos/octo/upstream/forks"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn token_env_on_the_command_line_outranks_the_config_token_file() {
+    let server = MockServer::start().await;
+    mount_upstream(&server).await;
+    let dir = scratch_dir("token-env");
+    std::fs::write(dir.join("token"), "tokenfromfile\n").unwrap();
+    std::fs::write(dir.join("config.toml"), "token-file = \"token\"\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_forklift"))
+        .current_dir(&dir)
+        .env_remove("GITHUB_TOKEN")
+        .env("GH_PAT", "fromenv")
+        .args([
+            "--config",
+            "config.toml",
+            "--token-env",
+            "GH_PAT",
+            "--count-only",
+        ])
+        .args(["--base-url", &server.uri()])
+        .arg(format!("{}/octo/upstream", server.uri()))
+        .output()
+        .unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let requests = server.received_requests().await.unwrap();
+    assert!(!requests.is_empty());
+    for request in requests {
+        assert_eq!(
+            request.headers.get("authorization").unwrap(),
+            "Bearer fromenv",
+            "{}",
+            request.url
+        );
+    }
+}
+
+#[tokio::test]
+async fn github_token_is_trimmed_before_use() {
+    let server = MockServer::start().await;
+    mount_upstream(&server).await;
+    let dir = scratch_dir("trimmed");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_forklift"))
+        .current_dir(&dir)
+        .env("GITHUB_TOKEN", "fromenv\r\n")
+        .args(["--count-only", "--base-url", &server.uri()])
+        .arg(format!("{}/octo/upstream", server.uri()))
+        .output()
+        .unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(
+        requests[0].headers.get("authorization").unwrap(),
+        "Bearer fromenv"
+    );
+}
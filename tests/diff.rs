@@ -0,0 +1,88 @@
+//! Comparing current forks with those of an earlier report
+
+use forklift::{diff_forks, ForkRecord, PreviousFork};
+
+fn fork(id: Option<u64>, organization: &str, stars: u32) -> ForkRecord {
+    ForkRecord {
+        id,
+        organization: organization.to_string(),
+        fork_name: "upstream".to_string(),
+        url: format!("https://github.com/{}/upstream", organization),
+        stars,
+        watchers: 0,
+        open_issues: 0,
+        forks: 0,
+        language: None,
+        owner_type: "Organization".to_string(),
+        updated_at: None,
+        pushed_at: None,
+        archived: false,
+        visibility: "public".to_string(),
+        default_branch: Some("main".to_string()),
+        ahead: None,
+        behind: None,
+        open_prs: None,
+        depth: None,
+        parent: None,
+        license: None,
+        license_changed: None,
+        topics: None,
+        unexpected: None,
+    }
+}
+
+fn previous(id: Option<u64>, organization: &str, url: bool, stars: u32) -> PreviousFork {
+    PreviousFork {
+        id,
+        organization: Some(organization.to_string()),
+        fork_name: Some("upstream".to_string()),
+        url: url.then(|| format!("https://github.com/{}/upstream", organization)),
+        stars: Some(stars),
+        archived: Some(false),
+    }
+}
+
+#[test]
+fn diff_forks_matches_by_id_then_url_then_name() {
+    let before = [
+        // Transferred since, but the id still matches
+        previous(Some(1), "old-owner", true, 5),
+        // An older report without ids, matched by URL regardless of case
+        previous(None, "By-Url", true, 2),
+        // Written with --fields organization,fork_name,stars
+        previous(None, "by-name", false, 3),
+        previous(Some(4), "gone", true, 1),
+    ];
+    let now = [
+        fork(Some(1), "new-owner", 5),
+        fork(Some(2), "by-url", 7),
+        fork(Some(3), "BY-NAME", 3),
+        fork(Some(5), "fresh", 0),
+    ];
+
+    let diff = diff_forks("before.json", &before, &now);
+
+    assert_eq!(diff.added, [("fresh/upstream".to_string(), 0)]);
+    assert_eq!(diff.removed, [("gone/upstream".to_string(), Some(1))]);
+    assert_eq!(diff.changed.len(), 2);
+    assert_eq!(diff.changed[0].full_name, "new-owner/upstream");
+    assert_eq!(
+        diff.changed[0].renamed_from.as_deref(),
+        Some("old-owner/upstream")
+    );
+    assert_eq!(diff.changed[0].stars, None);
+    assert_eq!(diff.changed[1].full_name, "by-url/upstream");
+    assert_eq!(diff.changed[1].renamed_from, None);
+    assert_eq!(diff.changed[1].stars, Some((2, 7)));
+}
+
+#[test]
+fn diff_forks_never_matches_different_ids_by_url() {
+    let before = [previous(Some(1), "octo", true, 1)];
+    let now = [fork(Some(2), "octo", 1)];
+
+    let diff = diff_forks("before.json", &before, &now);
+
+    assert_eq!(diff.added.len(), 1);
+    assert_eq!(diff.removed.len(), 1);
+}
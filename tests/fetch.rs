@@ -0,0 +1,792 @@
+//! Fork fetching against a fake GitHub API served by wiremock
+
+use forklift::{
+    build_client, compute_divergence, fetch_all_forks, fetch_descendant_forks,
+    fetch_page_with_retry, filter_forks, parse_base_url, read_token_file, validate_token,
+    ClientOptions, FetchOptions, FilterOptions, ForkStream, ForkliftError, RetryPolicy,
+    MAX_PER_PAGE,
+};
+use octocrab::Octocrab;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncReadExt;
+use tokio::sync::Semaphore;
+use wiremock::matchers::{method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const FORKS_PATH: &str = "/repos/octo/upstream/forks";
+
+fn client(server: &MockServer) -> Octocrab {
+    build_client(&ClientOptions {
+        token: Some("test-token".to_string()),
+        api_base: Some(server.uri()),
+        ..ClientOptions::default()
+    })
+    .unwrap()
+}
+
+/// Retry quickly so failing tests don't sit through real backoff
+fn fast_retry(max_retries: u32) -> RetryPolicy {
+    RetryPolicy {
+        max_retries,
+        base_delay: Duration::from_millis(10),
+    }
+}
+
+/// A fork as the REST API lists it, owned by organization `org<id>`
+fn fork(id: u64) -> Value {
+    let login = format!("org{}", id);
+    let user = "https://api.github.com/users/x";
+    json!({
+        "id": id,
+        "name": "upstream",
+        "full_name": format!("{}/upstream", login),
+        "url": format!("https://api.github.com/repos/{}/upstream", login),
+        "html_url": format!("https://github.com/{}/upstream", login),
+        "stargazers_count": id,
+        "archived": false,
+        "private": false,
+        "visibility": "public",
+        "default_branch": "main",
+        "pushed_at": "2026-01-01T00:00:00Z",
+        "owner": {
+            "login": login, "id": id, "node_id": "x", "type": "Organization", "site_admin": false,
+            "avatar_url": user, "gravatar_id": "", "url": user, "html_url": user,
+            "followers_url": user, "following_url": user, "gists_url": user, "starred_url": user,
+            "subscriptions_url": user, "organizations_url": user, "repos_url": user,
+            "events_url": user, "received_events_url": user
+        }
+    })
+}
+
+/// Serve page `page` of `pages`, holding `ids`, with GitHub's `Link` header
+async fn mount_page(server: &MockServer, page: u32, pages: u32, ids: &[u64]) {
+    let mut response = ResponseTemplate::new(200)
+        .set_body_json(ids.iter().map(|&id| fork(id)).collect::<Vec<_>>());
+    if page < pages {
+        let link = |n: u32| format!("<{}{}?per_page=100&page={}>", server.uri(), FORKS_PATH, n);
+        response = response.insert_header(
+            "link",
+            format!(
+                "{}; rel=\"next\", {}; rel=\"last\"",
+                link(page + 1),
+                link(pages)
+            )
+            .as_str(),
+        );
+    }
+    Mock::given(method("GET"))
+        .and(path(FORKS_PATH))
+        .and(query_param("page", page.to_string()))
+        .respond_with(response)
+        .mount(server)
+        .await;
+}
+
+/// Serve page `page` of `pages` linking only to the next page, as when
+/// GitHub doesn't expose the page count
+async fn mount_page_without_last(server: &MockServer, page: u32, pages: u32, ids: &[u64]) {
+    let mut response = ResponseTemplate::new(200)
+        .set_body_json(ids.iter().map(|&id| fork(id)).collect::<Vec<_>>());
+    if page < pages {
+        let next = format!(
+            "<{}{}?per_page=100&page={}>; rel=\"next\"",
+            server.uri(),
+            FORKS_PATH,
+            page + 1
+        );
+        response = response.insert_header("link", next.as_str());
+    }
+    Mock::given(method("GET"))
+        .and(path(FORKS_PATH))
+        .and(query_param("page", page.to_string()))
+        .respond_with(response)
+        .mount(server)
+        .await;
+}
+
+fn rate_limited() -> ResponseTemplate {
+    ResponseTemplate::new(403)
+        .insert_header("retry-after", "0")
+        .set_body_json(json!({
+            "message": "API rate limit exceeded for user ID 1.",
+            "documentation_url": "https://docs.github.com/rest/rate-limit"
+        }))
+}
+
+async fn forks_requests(server: &MockServer) -> usize {
+    let requests = server.received_requests().await.unwrap();
+    requests
+        .iter()
+        .filter(|request| request.url.path() == FORKS_PATH)
+        .count()
+}
+
+#[tokio::test]
+async fn fetch_all_forks_assembles_every_page() {
+    let server = MockServer::start().await;
+    mount_page(&server, 1, 3, &[1, 2]).await;
+    mount_page(&server, 2, 3, &[3, 4]).await;
+    mount_page(&server, 3, 3, &[5]).await;
+
+    let fetched = fetch_all_forks(
+        &client(&server),
+        "octo",
+        "upstream",
+        &FetchOptions::default(),
+    )
+    .await
+    .unwrap();
+
+    let mut ids: Vec<u64> = fetched.forks.iter().map(|fork| fork.id.0).collect();
+    ids.sort_unstable();
+    assert_eq!(ids, [1, 2, 3, 4, 5]);
+    assert!(fetched.failed_pages.is_empty());
+    assert_eq!(forks_requests(&server).await, 3);
+}
+
+#[tokio::test]
+async fn fetch_all_forks_fetches_a_single_page_once() {
+    let server = MockServer::start().await;
+    mount_page(&server, 1, 1, &[1, 2]).await;
+
+    let fetched = fetch_all_forks(
+        &client(&server),
+        "octo",
+        "upstream",
+        &FetchOptions::default(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(fetched.fetched, 2);
+    assert_eq!(fetched.forks.len(), 2);
+    assert_eq!(forks_requests(&server).await, 1);
+}
+
+#[tokio::test]
+async fn fetch_all_forks_handles_a_last_link_to_the_first_page() {
+    let server = MockServer::start().await;
+    let link = format!(
+        "<{}{}?per_page=100&page=1>; rel=\"last\"",
+        server.uri(),
+        FORKS_PATH
+    );
+    Mock::given(method("GET"))
+        .and(path(FORKS_PATH))
+        .and(query_param("page", "1"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(vec![fork(1), fork(2), fork(3)])
+                .insert_header("link", link.as_str()),
+        )
+        .mount(&server)
+        .await;
+
+    let fetched = fetch_all_forks(
+        &client(&server),
+        "octo",
+        "upstream",
+        &FetchOptions::default(),
+    )
+    .await
+    .unwrap();
+
+    let mut ids: Vec<u64> = fetched.forks.iter().map(|fork| fork.id.0).collect();
+    ids.sort_unstable();
+    assert_eq!(ids, [1, 2, 3]);
+    assert_eq!(forks_requests(&server).await, 1);
+}
+
+#[tokio::test]
+async fn fetch_all_forks_fetches_exactly_two_pages() {
+    let server = MockServer::start().await;
+    mount_page(&server, 1, 2, &[1, 2]).await;
+    mount_page(&server, 2, 2, &[3]).await;
+
+    let fetched = fetch_all_forks(
+        &client(&server),
+        "octo",
+        "upstream",
+        &FetchOptions::default(),
+    )
+    .await
+    .unwrap();
+
+    let mut ids: Vec<u64> = fetched.forks.iter().map(|fork| fork.id.0).collect();
+    ids.sort_unstable();
+    assert_eq!(ids, [1, 2, 3]);
+    assert!(fetched.failed_pages.is_empty());
+    assert_eq!(forks_requests(&server).await, 2);
+}
+
+#[tokio::test]
+async fn fetch_all_forks_drops_forks_repeated_across_pages() {
+    let server = MockServer::start().await;
+    mount_page(&server, 1, 2, &[1, 2]).await;
+    mount_page(&server, 2, 2, &[2, 3]).await;
+
+    let fetched = fetch_all_forks(
+        &client(&server),
+        "octo",
+        "upstream",
+        &FetchOptions::default(),
+    )
+    .await
+    .unwrap();
+
+    let mut ids: Vec<u64> = fetched.forks.iter().map(|fork| fork.id.0).collect();
+    ids.sort_unstable();
+    assert_eq!(ids, [1, 2, 3]);
+}
+
+#[tokio::test]
+async fn fork_stream_yields_every_fork_once() {
+    let server = MockServer::start().await;
+    mount_page(&server, 1, 3, &[1, 2]).await;
+    mount_page(&server, 2, 3, &[2, 3]).await;
+    mount_page(&server, 3, 3, &[4]).await;
+
+    let mut stream =
+        ForkStream::start(client(&server), "octo", "upstream", FetchOptions::default());
+    let mut ids = Vec::new();
+    while let Some(fork) = stream.next().await {
+        ids.push(fork.unwrap().id.0);
+    }
+
+    ids.sort_unstable();
+    assert_eq!(ids, [1, 2, 3, 4]);
+    let outcome = stream.outcome().unwrap();
+    assert_eq!(outcome.fetched, 4);
+    assert!(outcome.forks.is_empty());
+}
+
+#[tokio::test]
+async fn fork_stream_yields_the_error_of_a_failed_fetch() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(FORKS_PATH))
+        .respond_with(ResponseTemplate::new(404).set_body_json(json!({"message": "Not Found"})))
+        .mount(&server)
+        .await;
+
+    let mut stream =
+        ForkStream::start(client(&server), "octo", "upstream", FetchOptions::default());
+
+    assert!(stream.next().await.unwrap().is_err());
+    assert!(stream.next().await.is_none());
+    assert!(stream.outcome().is_none());
+}
+
+#[tokio::test]
+async fn fetch_all_forks_follows_next_links_without_a_last_link() {
+    let server = MockServer::start().await;
+    mount_page_without_last(&server, 1, 3, &[1, 2]).await;
+    mount_page_without_last(&server, 2, 3, &[3, 4]).await;
+    mount_page_without_last(&server, 3, 3, &[5]).await;
+
+    let fetched = fetch_all_forks(
+        &client(&server),
+        "octo",
+        "upstream",
+        &FetchOptions::default(),
+    )
+    .await
+    .unwrap();
+
+    let mut ids: Vec<u64> = fetched.forks.iter().map(|fork| fork.id.0).collect();
+    ids.sort_unstable();
+    assert_eq!(ids, [1, 2, 3, 4, 5]);
+    assert_eq!(forks_requests(&server).await, 3);
+}
+
+#[tokio::test]
+async fn fetch_page_with_retry_retries_after_a_rate_limit() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(FORKS_PATH))
+        .respond_with(rate_limited())
+        .up_to_n_times(2)
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    mount_page(&server, 1, 1, &[7]).await;
+
+    let page = fetch_page_with_retry(
+        client(&server),
+        "octo".to_string(),
+        "upstream".to_string(),
+        1,
+        MAX_PER_PAGE,
+        fast_retry(3),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(page.items.len(), 1);
+    assert_eq!(page.items[0].id.0, 7);
+    assert_eq!(forks_requests(&server).await, 3);
+}
+
+#[tokio::test]
+async fn fetch_page_with_retry_retries_server_errors() {
+    let server = MockServer::start().await;
+    for status in [502, 503] {
+        Mock::given(method("GET"))
+            .and(path(FORKS_PATH))
+            .respond_with(ResponseTemplate::new(status))
+            .up_to_n_times(1)
+            .with_priority(1)
+            .mount(&server)
+            .await;
+    }
+    mount_page(&server, 1, 1, &[7]).await;
+
+    let page = fetch_page_with_retry(
+        client(&server),
+        "octo".to_string(),
+        "upstream".to_string(),
+        1,
+        MAX_PER_PAGE,
+        fast_retry(3),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(page.items[0].id.0, 7);
+    assert_eq!(forks_requests(&server).await, 3);
+}
+
+#[tokio::test]
+async fn fetch_page_with_retry_gives_up_after_max_retries() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(FORKS_PATH))
+        .respond_with(rate_limited())
+        .mount(&server)
+        .await;
+
+    let result = fetch_page_with_retry(
+        client(&server),
+        "octo".to_string(),
+        "upstream".to_string(),
+        1,
+        MAX_PER_PAGE,
+        fast_retry(2),
+    )
+    .await;
+
+    let err = result.unwrap_err();
+    assert!(
+        matches!(&err, octocrab::Error::GitHub { source, .. } if source.status_code == 403),
+        "unexpected error: {:?}",
+        err
+    );
+    assert_eq!(forks_requests(&server).await, 3);
+}
+
+#[tokio::test]
+async fn fetch_page_with_retry_does_not_retry_other_errors() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(FORKS_PATH))
+        .respond_with(ResponseTemplate::new(404).set_body_json(json!({
+            "message": "Not Found",
+            "documentation_url": "https://docs.github.com/rest"
+        })))
+        .mount(&server)
+        .await;
+
+    let result = fetch_page_with_retry(
+        client(&server),
+        "octo".to_string(),
+        "upstream".to_string(),
+        1,
+        MAX_PER_PAGE,
+        fast_retry(3),
+    )
+    .await;
+
+    assert!(result.is_err());
+    assert_eq!(forks_requests(&server).await, 1);
+}
+
+/// Fetch the first fork page from `api_base`, retrying once after a backoff
+/// of at least half of `base_delay`, and time how long giving up took
+async fn time_failed_fetch(api_base: String, base_delay: Duration) -> Duration {
+    let octocrab = build_client(&ClientOptions {
+        token: Some("test-token".to_string()),
+        api_base: Some(api_base),
+        ..ClientOptions::default()
+    })
+    .unwrap();
+    let retry = RetryPolicy {
+        max_retries: 1,
+        base_delay,
+    };
+    let started = Instant::now();
+    let result = fetch_page_with_retry(
+        octocrab,
+        "octo".to_string(),
+        "upstream".to_string(),
+        1,
+        MAX_PER_PAGE,
+        retry,
+    )
+    .await;
+    assert!(result.is_err());
+    started.elapsed()
+}
+
+#[tokio::test]
+async fn fetch_page_with_retry_retries_a_refused_connection() {
+    // A port nobody listens on once the listener is dropped
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+
+    let elapsed = time_failed_fetch(
+        format!("http://127.0.0.1:{}", port),
+        Duration::from_millis(200),
+    )
+    .await;
+
+    assert!(elapsed >= Duration::from_millis(100), "{:?}", elapsed);
+}
+
+/// Connections made while fetching a page from a server that accepts every
+/// connection, reads the request, and hangs up without answering
+async fn connections_to_a_hanging_up_server(retry: RetryPolicy) -> usize {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let connections = Arc::new(AtomicUsize::new(0));
+    let accepted = connections.clone();
+    let server = tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            accepted.fetch_add(1, Ordering::SeqCst);
+            let mut request = [0; 4096];
+            let _ = socket.read(&mut request).await;
+        }
+    });
+    let octocrab = build_client(&ClientOptions {
+        token: Some("test-token".to_string()),
+        api_base: Some(format!("http://127.0.0.1:{}", port)),
+        ..ClientOptions::default()
+    })
+    .unwrap();
+
+    let result = fetch_page_with_retry(
+        octocrab,
+        "octo".to_string(),
+        "upstream".to_string(),
+        1,
+        MAX_PER_PAGE,
+        retry,
+    )
+    .await;
+    server.abort();
+    assert!(result.is_err());
+    connections.load(Ordering::SeqCst)
+}
+
+#[tokio::test]
+async fn fetch_page_with_retry_retries_a_connection_closed_before_the_response() {
+    let once = connections_to_a_hanging_up_server(fast_retry(0)).await;
+    let retried = connections_to_a_hanging_up_server(fast_retry(1)).await;
+
+    assert!(once > 0);
+    assert_eq!(retried, 2 * once);
+}
+
+#[tokio::test]
+async fn fetch_page_with_retry_does_not_retry_a_tls_failure() {
+    // Speaking TLS to a plain HTTP server fails the handshake every time
+    let server = MockServer::start().await;
+
+    let elapsed = time_failed_fetch(
+        server.uri().replace("http://", "https://"),
+        Duration::from_secs(10),
+    )
+    .await;
+
+    assert!(elapsed < Duration::from_secs(5), "{:?}", elapsed);
+}
+
+#[tokio::test]
+async fn keep_partial_skips_a_page_that_keeps_failing() {
+    let server = MockServer::start().await;
+    mount_page(&server, 1, 3, &[1]).await;
+    Mock::given(method("GET"))
+        .and(path(FORKS_PATH))
+        .and(query_param("page", "2"))
+        .respond_with(rate_limited())
+        .mount(&server)
+        .await;
+    mount_page(&server, 3, 3, &[3]).await;
+
+    let options = FetchOptions {
+        retry: fast_retry(1),
+        keep_partial: true,
+        ..FetchOptions::default()
+    };
+    let fetched = fetch_all_forks(&client(&server), "octo", "upstream", &options)
+        .await
+        .unwrap();
+
+    let mut ids: Vec<u64> = fetched.forks.iter().map(|fork| fork.id.0).collect();
+    ids.sort_unstable();
+    assert_eq!(ids, [1, 3]);
+    assert_eq!(fetched.failed_pages, [2]);
+}
+
+#[tokio::test]
+async fn validate_token_rejects_only_bad_credentials() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/rate_limit"))
+        .respond_with(ResponseTemplate::new(401).set_body_json(json!({
+            "message": "Bad credentials",
+            "documentation_url": "https://docs.github.com/rest"
+        })))
+        .mount(&server)
+        .await;
+
+    let result = validate_token(&client(&server)).await;
+
+    assert!(matches!(result, Err(ForkliftError::InvalidToken)));
+}
+
+#[tokio::test]
+async fn validate_token_tells_a_forbidden_preflight_from_a_bad_token() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/rate_limit"))
+        .respond_with(ResponseTemplate::new(403).set_body_json(json!({
+            "message": "Resource not accessible by personal access token",
+            "documentation_url": "https://docs.github.com/rest"
+        })))
+        .mount(&server)
+        .await;
+    mount_page(&server, 1, 1, &[1]).await;
+
+    let octocrab = client(&server);
+    let result = validate_token(&octocrab).await;
+    let fetched = fetch_all_forks(&octocrab, "octo", "upstream", &FetchOptions::default())
+        .await
+        .unwrap();
+
+    assert!(matches!(result, Err(e) if !matches!(e, ForkliftError::InvalidToken)));
+    assert_eq!(fetched.forks.len(), 1);
+}
+
+#[tokio::test]
+async fn private_forks_are_kept_only_with_include_private() {
+    let server = MockServer::start().await;
+    let mut private = fork(2);
+    private["private"] = json!(true);
+    private["visibility"] = json!("private");
+    Mock::given(method("GET"))
+        .and(path(FORKS_PATH))
+        .respond_with(ResponseTemplate::new(200).set_body_json(vec![fork(1), private]))
+        .mount(&server)
+        .await;
+
+    let fetched = fetch_all_forks(
+        &client(&server),
+        "octo",
+        "upstream",
+        &FetchOptions::default(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(fetched.forks.len(), 2);
+
+    let public_only = filter_forks(fetched.forks.clone(), &FilterOptions::default());
+    assert_eq!(public_only.private_left_out, 1);
+    let names: Vec<&str> = public_only
+        .records
+        .iter()
+        .map(|f| f.organization.as_str())
+        .collect();
+    assert_eq!(names, ["org1"]);
+
+    let options = FilterOptions {
+        include_private: true,
+        ..FilterOptions::default()
+    };
+    let with_private = filter_forks(fetched.forks, &options);
+    assert_eq!(with_private.private_left_out, 0);
+    let visibility: Vec<&str> = with_private
+        .records
+        .iter()
+        .map(|f| f.visibility.as_str())
+        .collect();
+    assert_eq!(visibility, ["public", "private"]);
+}
+
+#[tokio::test]
+async fn enterprise_base_url_keeps_every_request_under_api_v3() {
+    let server = MockServer::start().await;
+    let (_, api_base) = parse_base_url(&server.uri()).unwrap();
+    let enterprise_forks = format!("/api/v3{}", FORKS_PATH);
+    // Without a last link, the next link itself is followed
+    let next = format!(
+        "<{}{}?per_page=100&page=2>; rel=\"next\"",
+        api_base, FORKS_PATH
+    );
+    Mock::given(method("GET"))
+        .and(path(enterprise_forks.as_str()))
+        .and(query_param("page", "1"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(vec![fork(1)])
+                .insert_header("link", next.as_str()),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path(enterprise_forks.as_str()))
+        .and(query_param("page", "2"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(vec![fork(2)]))
+        .mount(&server)
+        .await;
+
+    let octocrab = build_client(&ClientOptions {
+        token: Some("test-token".to_string()),
+        api_base: Some(api_base),
+        ..ClientOptions::default()
+    })
+    .unwrap();
+    let fetched = fetch_all_forks(&octocrab, "octo", "upstream", &FetchOptions::default())
+        .await
+        .unwrap();
+
+    assert_eq!(fetched.forks.len(), 2);
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 2);
+    for request in requests {
+        assert_eq!(request.url.path(), enterprise_forks, "{}", request.url);
+    }
+}
+
+#[test]
+fn build_client_rejects_a_token_with_a_line_break() {
+    let result = build_client(&ClientOptions {
+        token: Some("test-token\r\n".to_string()),
+        ..ClientOptions::default()
+    });
+
+    assert!(matches!(result, Err(ForkliftError::MalformedToken)));
+}
+
+#[test]
+fn read_token_file_names_a_missing_file() {
+    let path = std::env::temp_dir().join(format!("forklift-no-token-{}", std::process::id()));
+    let path = path.to_str().unwrap();
+
+    let error = read_token_file(path).unwrap_err();
+
+    assert!(
+        matches!(&error, ForkliftError::UnreadableTokenFile { path: p, .. } if p == path),
+        "{:?}",
+        error
+    );
+    assert!(error.to_string().contains(path), "{}", error);
+}
+
+/// Serve `children` as the only page of forks of `org<parent>/upstream`,
+/// after `delay`
+async fn mount_children(server: &MockServer, parent: u64, children: &[u64], delay: Duration) {
+    Mock::given(method("GET"))
+        .and(path(format!("/repos/org{}/upstream/forks", parent)))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(children.iter().map(|&id| fork(id)).collect::<Vec<_>>())
+                .set_delay(delay),
+        )
+        .mount(server)
+        .await;
+}
+
+/// A direct fork of upstream that GitHub says has forks of its own
+fn forked_fork(id: u64) -> octocrab::models::Repository {
+    let mut fork = fork(id);
+    fork["forks_count"] = json!(3);
+    serde_json::from_value(fork).unwrap()
+}
+
+#[tokio::test]
+async fn fetch_descendant_forks_reports_a_deadline_hit_at_depth_2() {
+    let server = MockServer::start().await;
+    mount_children(&server, 1, &[11, 12], Duration::from_secs(5)).await;
+
+    let options = FetchOptions {
+        deadline: Some(tokio::time::Instant::now() + Duration::from_millis(200)),
+        ..FetchOptions::default()
+    };
+    let descendants =
+        fetch_descendant_forks(&client(&server), &[forked_fork(1)], 3, false, &options)
+            .await
+            .unwrap();
+
+    assert!(descendants.timed_out);
+    assert!(descendants.records.is_empty());
+}
+
+#[tokio::test]
+async fn fetch_descendant_forks_caps_the_whole_tree_at_max_forks() {
+    let server = MockServer::start().await;
+    mount_children(&server, 1, &[11, 12, 13], Duration::ZERO).await;
+    mount_children(&server, 2, &[21, 22, 23], Duration::ZERO).await;
+
+    let options = FetchOptions {
+        max_forks: Some(4),
+        ..FetchOptions::default()
+    };
+    let direct = [forked_fork(1), forked_fork(2)];
+    let descendants = fetch_descendant_forks(&client(&server), &direct, 2, false, &options)
+        .await
+        .unwrap();
+
+    assert!(descendants.limited);
+    assert_eq!(descendants.records.len(), 2);
+}
+
+#[tokio::test]
+async fn compute_divergence_encodes_branch_names_in_the_compare_route() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(
+            "/repos/octo/upstream/compare/release/v1%232...org1:fix/issue%2342",
+        ))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(json!({"ahead_by": 3, "behind_by": 5})),
+        )
+        .mount(&server)
+        .await;
+
+    let mut repository = fork(1);
+    repository["default_branch"] = json!("fix/issue#42");
+    let mut forks = filter_forks(
+        vec![serde_json::from_value(repository).unwrap()],
+        &FilterOptions::default(),
+    )
+    .records;
+    compute_divergence(
+        &client(&server),
+        "octo",
+        "upstream",
+        "release/v1#2",
+        &mut forks,
+        &Arc::new(Semaphore::new(1)),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(forks[0].ahead, Some(3));
+    assert_eq!(forks[0].behind, Some(5));
+}
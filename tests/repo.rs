@@ -0,0 +1,207 @@
+//! Repository URL parsing
+
+use forklift::{parse_base_url, parse_github_url, ForkliftError, RepoInfo, GITHUB_HOST};
+use proptest::prelude::*;
+
+fn repo(owner: &str, name: &str) -> RepoInfo {
+    RepoInfo {
+        owner: owner.to_string(),
+        name: name.to_string(),
+    }
+}
+
+fn parse(raw: &str) -> Result<RepoInfo, ForkliftError> {
+    parse_github_url(raw, GITHUB_HOST)
+}
+
+#[test]
+fn accepts_the_documented_forms() {
+    for raw in [
+        "https://github.com/kubernetes/kubernetes",
+        "http://github.com/kubernetes/kubernetes",
+        "github.com/kubernetes/kubernetes",
+        "git@github.com:kubernetes/kubernetes.git",
+        "ssh://git@github.com/kubernetes/kubernetes.git",
+    ] {
+        assert_eq!(
+            parse(raw).unwrap(),
+            repo("kubernetes", "kubernetes"),
+            "{}",
+            raw
+        );
+    }
+}
+
+#[test]
+fn accepts_owner_repo_shorthand() {
+    for (raw, expected) in [
+        ("kubernetes/kubernetes", repo("kubernetes", "kubernetes")),
+        ("rust-lang/cargo/", repo("rust-lang", "cargo")),
+        ("helm/helm.git", repo("helm", "helm")),
+        ("octo-org/socket.io", repo("octo-org", "socket.io")),
+    ] {
+        assert_eq!(parse(raw).unwrap(), expected, "{:?}", raw);
+    }
+    assert_eq!(
+        parse_github_url("infra/tools", "github.mycorp.com").unwrap(),
+        repo("infra", "tools")
+    );
+}
+
+#[test]
+fn does_not_mistake_a_host_for_an_owner() {
+    assert!(matches!(
+        parse("github.com/kubernetes"),
+        Err(ForkliftError::InvalidPathSegments(_))
+    ));
+    assert!(matches!(
+        parse("gitlab.com/kubernetes"),
+        Err(ForkliftError::InvalidDomain { .. })
+    ));
+    assert!(parse("kubernetes").is_err());
+}
+
+#[test]
+fn ignores_what_follows_owner_and_repo() {
+    for raw in [
+        "https://github.com/rust-lang/cargo/",
+        "https://github.com/rust-lang/cargo.git",
+        "https://github.com/rust-lang/cargo/tree/master/src",
+        "https://github.com/rust-lang/cargo/pulls?q=is%3Aopen",
+        "https://github.com/rust-lang/cargo#readme",
+        "https://github.com//rust-lang//cargo",
+        "  https://github.com/rust-lang/cargo\n",
+    ] {
+        assert_eq!(parse(raw).unwrap(), repo("rust-lang", "cargo"), "{:?}", raw);
+    }
+}
+
+#[test]
+fn ignores_host_case_www_and_ports() {
+    for raw in [
+        "https://GitHub.com/rust-lang/cargo",
+        "HTTPS://GITHUB.COM/rust-lang/cargo",
+        "https://www.github.com/rust-lang/cargo",
+        "www.github.com/rust-lang/cargo",
+        "https://github.com:443/rust-lang/cargo",
+        "git@GitHub.com:rust-lang/cargo.git",
+    ] {
+        assert_eq!(parse(raw).unwrap(), repo("rust-lang", "cargo"), "{:?}", raw);
+    }
+}
+
+#[test]
+fn keeps_owner_and_repo_case() {
+    assert_eq!(
+        parse("https://github.com/RustLang/Cargo").unwrap(),
+        repo("RustLang", "Cargo")
+    );
+}
+
+#[test]
+fn rejects_other_hosts() {
+    for raw in [
+        "https://gitlab.com/rust-lang/cargo",
+        "https://github.com.evil.example/rust-lang/cargo",
+        "https://api.github.com/repos/rust-lang/cargo",
+        "git@gitlab.com:rust-lang/cargo.git",
+    ] {
+        assert!(
+            matches!(parse(raw), Err(ForkliftError::InvalidDomain { .. })),
+            "{:?}",
+            raw
+        );
+    }
+}
+
+#[test]
+fn rejects_urls_without_owner_and_repo() {
+    for raw in [
+        "https://github.com",
+        "https://github.com/rust-lang",
+        "github.com//",
+    ] {
+        assert!(
+            matches!(parse(raw), Err(ForkliftError::InvalidPathSegments(_))),
+            "{:?}",
+            raw
+        );
+    }
+    assert!(matches!(
+        parse("git@github.com"),
+        Err(ForkliftError::InvalidUrl(_))
+    ));
+}
+
+#[test]
+fn matches_an_enterprise_host() {
+    let (host, api_base) = parse_base_url("github.mycorp.com").unwrap();
+    assert_eq!(host, "github.mycorp.com");
+    assert_eq!(api_base, "https://github.mycorp.com/api/v3");
+    assert_eq!(
+        parse_github_url("https://github.mycorp.com/infra/tools", &host).unwrap(),
+        repo("infra", "tools")
+    );
+    assert!(matches!(
+        parse("https://github.mycorp.com/infra/tools"),
+        Err(ForkliftError::InvalidDomain { .. })
+    ));
+}
+
+/// GitHub owner names: alphanumerics and inner hyphens
+fn owner() -> impl Strategy<Value = String> {
+    "[A-Za-z0-9][A-Za-z0-9-]{0,38}"
+}
+
+/// Repository names, minus a `.git` suffix that parsing would strip
+fn name() -> impl Strategy<Value = String> {
+    "[A-Za-z0-9_][A-Za-z0-9._-]{0,40}".prop_filter("ends in .git", |name| !name.ends_with(".git"))
+}
+
+proptest! {
+    #[test]
+    fn parses_any_spelling_of_a_repository_url(
+        owner in owner(),
+        name in name(),
+        scheme in prop::sample::select(vec!["", "http://", "https://", "HTTPS://"]),
+        host in prop::sample::select(vec!["github.com", "GitHub.com", "www.github.com", "github.com:443"]),
+        suffix in prop::sample::select(vec![
+            "", "/", ".git", ".git/", "/tree/main/src", "/blob/main/README.md", "?tab=readme-ov-file",
+            "#readme", "/pulls?page=2",
+        ]),
+    ) {
+        let raw = format!("{}{}/{}/{}{}", scheme, host, owner, name, suffix);
+        prop_assert_eq!(parse(&raw).unwrap(), repo(&owner, &name), "{}", raw);
+    }
+
+    #[test]
+    fn parses_owner_repo_shorthand(owner in owner(), name in name()) {
+        let raw = format!("{}/{}", owner, name);
+        prop_assert_eq!(parse(&raw).unwrap(), repo(&owner, &name));
+    }
+
+    #[test]
+    fn parses_ssh_clone_urls(owner in owner(), name in name(), git in any::<bool>()) {
+        let suffix = if git { ".git" } else { "" };
+        let scp = format!("git@github.com:{}/{}{}", owner, name, suffix);
+        prop_assert_eq!(parse(&scp).unwrap(), repo(&owner, &name));
+        let ssh = format!("ssh://git@github.com/{}/{}{}", owner, name, suffix);
+        prop_assert_eq!(parse(&ssh).unwrap(), repo(&owner, &name));
+    }
+
+    #[test]
+    fn rejects_any_other_host(
+        host in "[a-z]{1,12}\\.(com|org|io)".prop_filter("github.com", |host| host != GITHUB_HOST),
+        owner in owner(),
+        name in name(),
+    ) {
+        let raw = format!("https://{}/{}/{}", host, owner, name);
+        let is_invalid_domain = matches!(parse(&raw), Err(ForkliftError::InvalidDomain { .. }));
+        prop_assert!(is_invalid_domain, "{}", raw);
+    }
+
+    #[test]
+    fn never_panics(raw in any::<String>()) {
+        let _ = parse(&raw);
+    }
+}
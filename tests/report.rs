@@ -0,0 +1,205 @@
+//! Report rendering
+
+use forklift::{
+    write_results, ClientOptions, ForkRecord, OutputFormat, PostSink, ReportOptions, S3Location,
+    S3Sink, UrlKind,
+};
+use std::path::PathBuf;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn fork(organization: &str, fork_name: &str) -> ForkRecord {
+    ForkRecord {
+        id: None,
+        organization: organization.to_string(),
+        fork_name: fork_name.to_string(),
+        url: format!("https://github.com/{}/{}", organization, fork_name),
+        stars: 1,
+        watchers: 0,
+        open_issues: 0,
+        forks: 0,
+        language: None,
+        owner_type: "Organization".to_string(),
+        updated_at: None,
+        pushed_at: None,
+        archived: false,
+        visibility: "public".to_string(),
+        default_branch: Some("main".to_string()),
+        ahead: None,
+        behind: None,
+        open_prs: None,
+        depth: None,
+        parent: None,
+        license: None,
+        license_changed: None,
+        topics: None,
+        unexpected: None,
+    }
+}
+
+/// Render `forks` as a Markdown report and return its table rows
+async fn markdown_rows(name: &str, forks: &[ForkRecord]) -> Vec<String> {
+    let path: PathBuf =
+        std::env::temp_dir().join(format!("forklift-{}-{}.md", name, std::process::id()));
+    let options = ReportOptions {
+        format: OutputFormat::Markdown,
+        ..ReportOptions::default()
+    };
+    write_results(path.to_str().unwrap(), "octo", "upstream", forks, &options)
+        .await
+        .unwrap();
+    let report = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    report
+        .lines()
+        .filter(|line| line.starts_with("| ") && !line.starts_with("| Organization"))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Split a Markdown table row on the pipes that aren't escaped
+fn cells(row: &str) -> Vec<String> {
+    let mut cells = vec![String::new()];
+    let mut chars = row.trim().trim_matches('|').chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                cells.last_mut().unwrap().push(c);
+                cells.last_mut().unwrap().extend(chars.next());
+            }
+            '|' => cells.push(String::new()),
+            c => cells.last_mut().unwrap().push(c),
+        }
+    }
+    cells.iter().map(|cell| cell.trim().to_string()).collect()
+}
+
+#[tokio::test]
+async fn markdown_escapes_pipes_in_cells() {
+    let rows = markdown_rows("pipes", &[fork("octo-org", "fork|name")]).await;
+
+    assert_eq!(rows.len(), 1);
+    let cells = cells(&rows[0]);
+    assert_eq!(cells.len(), 8, "{}", rows[0]);
+    assert_eq!(cells[0], "octo-org");
+    assert_eq!(cells[1], "fork\\|name");
+    assert_eq!(cells[2], "https://github.com/octo-org/fork\\|name");
+}
+
+#[tokio::test]
+async fn markdown_escapes_formatting_characters() {
+    let rows = markdown_rows("formatting", &[fork("my_org", "`code`*bold*")]).await;
+
+    let cells = cells(&rows[0]);
+    assert_eq!(cells[0], "my\\_org");
+    assert_eq!(cells[1], "\\`code\\`\\*bold\\*");
+    assert_eq!(cells[2], "https://github.com/my_org/`code`*bold*");
+}
+
+#[tokio::test]
+async fn markdown_bolds_unexpected_owners() {
+    let mut unexpected = fork("new_org", "upstream");
+    unexpected.unexpected = Some(true);
+    let mut expected = fork("approved", "upstream");
+    expected.unexpected = Some(false);
+    let rows = markdown_rows("unexpected", &[expected, unexpected]).await;
+
+    assert_eq!(cells(&rows[0])[0], "approved");
+    assert_eq!(cells(&rows[1])[0], "**new\\_org**");
+}
+
+#[tokio::test]
+async fn urls_lists_one_url_per_fork() {
+    let path: PathBuf =
+        std::env::temp_dir().join(format!("forklift-urls-{}.txt", std::process::id()));
+    let forks = [fork("octo-org", "upstream"), fork("other-org", "upstream")];
+    let options = ReportOptions {
+        format: OutputFormat::Urls,
+        url_kind: UrlKind::Clone,
+        ..ReportOptions::default()
+    };
+    write_results(path.to_str().unwrap(), "octo", "upstream", &forks, &options)
+        .await
+        .unwrap();
+    let report = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(
+        report,
+        "https://github.com/octo-org/upstream.git\nhttps://github.com/other-org/upstream.git\n"
+    );
+}
+
+#[tokio::test]
+async fn prometheus_counts_org_forks_cut_by_top() {
+    let path: PathBuf =
+        std::env::temp_dir().join(format!("forklift-top-{}.prom", std::process::id()));
+    // --top 1 of 3 organization forks out of 5 listed
+    let forks = [fork("octo-org", "upstream")];
+    let options = ReportOptions {
+        format: OutputFormat::Prometheus,
+        top_of: Some(3),
+        total_forks: Some(5),
+        org_forks: Some(3),
+        ..ReportOptions::default()
+    };
+    write_results(path.to_str().unwrap(), "octo", "upstream", &forks, &options)
+        .await
+        .unwrap();
+    let report = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(
+        report.contains("forklift_total_forks{repo=\"octo/upstream\"} 5\n"),
+        "{}",
+        report
+    );
+    assert!(
+        report.contains("forklift_org_forks{repo=\"octo/upstream\"} 3\n"),
+        "{}",
+        report
+    );
+}
+
+#[tokio::test]
+async fn posts_and_s3_uploads_go_through_the_proxy() {
+    // The hosts don't resolve, so only a request sent to the proxy can succeed
+    let proxy = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/hook"))
+        .respond_with(ResponseTemplate::new(204))
+        .mount(&proxy)
+        .await;
+    Mock::given(method("PUT"))
+        .and(path("/reports/forks.json"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&proxy)
+        .await;
+    let options = ClientOptions {
+        proxy: Some(proxy.uri()),
+        ..ClientOptions::default()
+    };
+
+    let post = PostSink::new("http://hooks.forklift.invalid/hook", &[], &options).unwrap();
+    post.post(&[fork("octo-org", "upstream")], None)
+        .await
+        .unwrap();
+
+    std::env::set_var("AWS_ACCESS_KEY_ID", "test-key");
+    std::env::set_var("AWS_SECRET_ACCESS_KEY", "test-secret");
+    std::env::set_var("AWS_ENDPOINT_URL_S3", "http://s3.forklift.invalid");
+    let s3 = S3Sink::from_env(&options).unwrap();
+    let location = S3Location::parse("s3://reports/forks.json")
+        .unwrap()
+        .unwrap();
+    s3.put(&location, b"[]".to_vec(), "application/json")
+        .await
+        .unwrap();
+
+    let requests = proxy.received_requests().await.unwrap();
+    let hosts: Vec<&str> = requests
+        .iter()
+        .filter_map(|request| request.headers.get("host")?.to_str().ok())
+        .collect();
+    assert_eq!(hosts, ["hooks.forklift.invalid", "s3.forklift.invalid"]);
+}